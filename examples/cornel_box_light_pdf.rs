@@ -2,7 +2,7 @@ use raytracer::camera::*;
 use raytracer::scene::*;
 use raytracer::shape::builder::CameraBuilder;
 use raytracer::shape::*;
-use raytracer::shape::mesh::Mesh;
+use raytracer::shape::cuboid::Cuboid;
 use raytracer::ui;
 
 fn main() -> eframe::Result {
@@ -65,12 +65,11 @@ fn main() -> eframe::Result {
 
     scene.world.push(sphere);
 
-    let mut cube = Mesh::new("assets/cube.obj", white);
-    cube.scale(Vec3::new(160.0, 320.0, 160.0));
+    let mut cube = Cuboid::new(Vec3::new(160.0, 320.0, 160.0), white);
     cube.rotate_y(15.0);
     cube.translate(Vec3::new(340.0, 160.0, 360.0));
 
-    scene.world.push(Shape::Mesh(cube));
+    scene.world.push(Shape::Cuboid(cube));
 
     scene.light = Some(
         Shape::list(vec![
@@ -2,7 +2,7 @@ use raytracer::camera::*;
 use raytracer::scene::*;
 use raytracer::shape::builder::CameraBuilder;
 use raytracer::shape::*;
-use raytracer::shape::mesh::Mesh;
+use raytracer::shape::cuboid::Cuboid;
 use raytracer::ui;
 
 fn main() -> eframe::Result {
@@ -58,19 +58,17 @@ fn main() -> eframe::Result {
         white.clone(),
     ));
 
-    let mut cube = Mesh::new("assets/cube.obj", white.clone());
-    cube.scale(Vec3::new(160.0, 160.0, 160.0));
+    let mut cube = Cuboid::new(Vec3::new(160.0, 160.0, 160.0), white.clone());
     cube.rotate_y(-15.0);
     cube.translate(Vec3::new(210.0, 80.0, 180.0));
 
-    scene.world.push(Shape::smoke_box(cube, 0.01, Vec3::splat(0.0)));
+    scene.world.push(Shape::constant_medium(Shape::Cuboid(cube), 0.01, Vec3::splat(0.0)));
 
-    let mut cube = Mesh::new("assets/cube.obj", white.clone());
-    cube.scale(Vec3::new(160.0, 320.0, 160.0));
+    let mut cube = Cuboid::new(Vec3::new(160.0, 320.0, 160.0), white.clone());
     cube.rotate_y(15.0);
     cube.translate(Vec3::new(340.0, 160.0, 360.0));
 
-    scene.world.push(Shape::smoke_box(cube, 0.01, Vec3::splat(1.0)));
+    scene.world.push(Shape::constant_medium(Shape::Cuboid(cube), 0.01, Vec3::splat(1.0)));
 
     ui::App::run(scene)
 }
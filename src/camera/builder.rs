@@ -1,5 +1,6 @@
 use crate::camera::*;
 
+#[derive(Clone)]
 pub struct CameraBuilder {
     position: Vec3,
     look_at: Vec3,
@@ -1,10 +1,29 @@
 pub use glam::f32::Vec3;
 
-#[derive(Copy, Clone, Default)]
+use crate::light::ALL_LIGHT_CHANNELS;
+
+/// What a ray is traced for, so that objects can be hidden from some of them.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub enum RayKind {
+    #[default]
+    Camera,
+    /// Scattered by a material with a distribution of directions.
+    Diffuse,
+    /// Reflected or refracted into a single direction by a mirror or glass.
+    Specular,
+    /// Cast towards a light to check whether it is blocked.
+    Shadow,
+}
+
+#[derive(Copy, Clone)]
 pub struct Ray {
     pub origin: Vec3,
     pub direction: Vec3,
     pub time: f32,
+    pub kind: RayKind,
+    /// The light channels of the surface the ray leaves. Emitters only light
+    /// it when they share one of these channels.
+    pub light_channels: u32,
 }
 
 impl Ray {
@@ -13,6 +32,8 @@ impl Ray {
             origin,
             direction,
             time: 0.0,
+            kind: RayKind::Camera,
+            light_channels: ALL_LIGHT_CHANNELS,
         }
     }
 
@@ -21,10 +42,24 @@ impl Ray {
             origin,
             direction,
             time,
+            kind: RayKind::Camera,
+            light_channels: ALL_LIGHT_CHANNELS,
         }
     }
 
+    pub fn with_kind(mut self, kind: RayKind, light_channels: u32) -> Self {
+        self.kind = kind;
+        self.light_channels = light_channels;
+        self
+    }
+
     pub fn at(&self, t: f32) -> Vec3 {
         self.origin + self.direction * t
     }
 }
+
+impl Default for Ray {
+    fn default() -> Self {
+        Self::new(Vec3::ZERO, Vec3::ZERO)
+    }
+}
@@ -0,0 +1,279 @@
+use glam::{Quat, Vec3};
+use image::ImageReader;
+use rand::Rng;
+use std::f32::consts::PI;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::error::{Error, Result};
+use crate::shape::environment_light::EnvironmentLight;
+use crate::shape::sphere::Sphere;
+use crate::shape::Shape;
+
+/// Light arriving from every direction, stored as a linear equirectangular
+/// image such as an `.hdr` or `.exr` panorama. The top row of the image is
+/// straight up and its center looks along `+z`.
+///
+/// Directions are sampled in proportion to the luminance of the pixels, so
+/// small bright features like the sun or studio softboxes are found quickly
+/// once `light` is added to `Scene::light`.
+#[derive(Clone)]
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    pixels: Arc<Vec<Vec3>>,
+    distribution: Arc<Distribution>,
+    rotation: Quat,
+    intensity: f32,
+}
+
+impl EnvironmentMap {
+    /// A map from `width * height` pixels in rows from top to bottom.
+    pub fn new(width: usize, height: usize, pixels: Vec<Vec3>) -> Self {
+        assert_eq!(
+            pixels.len(),
+            width * height,
+            "environment map needs one pixel per texel"
+        );
+        let distribution = Distribution::new(width, height, &pixels);
+        Self {
+            width,
+            height,
+            pixels: Arc::new(pixels),
+            distribution: Arc::new(distribution),
+            rotation: Quat::IDENTITY,
+            intensity: 1.0,
+        }
+    }
+
+    /// Loads any image format the `image` crate reads. HDR and OpenEXR keep
+    /// their full range; 8-bit images are taken as already linear.
+    pub fn load(path: &str) -> Result<Self> {
+        let path = Path::new(path);
+        let image = ImageReader::open(path)
+            .map_err(|error| Error::io(path, "environment map", error))?
+            .decode()
+            .map_err(|error| Error::image(path, "environment map", error))?
+            .into_rgb32f();
+
+        let pixels = image.pixels().map(|pixel| Vec3::from_array(pixel.0)).collect();
+        Ok(Self::new(image.width() as usize, image.height() as usize, pixels))
+    }
+
+    /// Turns the map by `angle` radians around the vertical axis.
+    pub fn with_rotation(mut self, angle: f32) -> Self {
+        self.rotation = Quat::from_rotation_y(angle);
+        self
+    }
+
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// A light to put in `Scene::light` so the map is importance sampled.
+    pub fn light(&self) -> Shape {
+        Shape::EnvironmentLight(EnvironmentLight::new(self.clone()))
+    }
+
+    /// The light arriving from `direction`, filtered bilinearly.
+    pub fn radiance(&self, direction: Vec3) -> Vec3 {
+        let (u, v) = Sphere::get_uv(self.rotation.inverse() * direction.normalize());
+        let x = u * self.width as f32 - 0.5;
+        let y = (v * self.height as f32 - 0.5).clamp(0.0, (self.height - 1) as f32);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+
+        let texel = |x: f32, y: f32| {
+            let x = (x as isize).rem_euclid(self.width as isize) as usize;
+            let y = (y as usize).min(self.height - 1);
+            self.pixels[y * self.width + x]
+        };
+        let top = texel(x0, y0).lerp(texel(x0 + 1.0, y0), fx);
+        let bottom = texel(x0, y0 + 1.0).lerp(texel(x0 + 1.0, y0 + 1.0), fx);
+
+        top.lerp(bottom, fy) * self.intensity
+    }
+
+    /// The density, per unit solid angle, of directions returned by `sample`.
+    pub fn pdf(&self, direction: Vec3) -> f32 {
+        let direction = self.rotation.inverse() * direction.normalize();
+        // Taken from the horizontal part, which is still accurate close to
+        // the poles.
+        let sin_theta = Vec3::new(direction.x, 0.0, direction.z).length();
+        if sin_theta == 0.0 {
+            return 0.0;
+        }
+
+        let (u, v) = Sphere::get_uv(direction);
+        let x = ((u * self.width as f32) as usize).min(self.width - 1);
+        let y = ((v * self.height as f32) as usize).min(self.height - 1);
+
+        self.distribution.density(x, y) / (2.0 * PI * PI * sin_theta)
+    }
+
+    pub fn sample(&self) -> Vec3 {
+        let mut rng = rand::thread_rng();
+        let (u, v) = self.distribution.sample(rng.random(), rng.random());
+
+        // The inverse of `Sphere::get_uv`.
+        let phi = (u - 0.5) * 2.0 * PI;
+        let (sin_theta, cos_theta) = (v * PI).sin_cos();
+        let direction = Vec3::new(sin_theta * phi.sin(), cos_theta, sin_theta * phi.cos());
+
+        self.rotation * direction
+    }
+}
+
+/// A piecewise constant distribution over the image: rows are picked by
+/// their total weight, then a column within the row.
+struct Distribution {
+    width: usize,
+    height: usize,
+    /// Cumulative weights of each row, `width + 1` entries per row.
+    conditional: Vec<f32>,
+    /// Cumulative weights of the rows, `height + 1` entries.
+    marginal: Vec<f32>,
+}
+
+impl Distribution {
+    fn new(width: usize, height: usize, pixels: &[Vec3]) -> Self {
+        let luminance = Vec3::new(0.2126, 0.7152, 0.0722);
+        // Rows near the poles cover less of the sphere.
+        let mut weights: Vec<f32> = (0..height)
+            .flat_map(|y| {
+                let sin_theta = ((y as f32 + 0.5) / height as f32 * PI).sin();
+                pixels[y * width..(y + 1) * width]
+                    .iter()
+                    .map(move |pixel| pixel.dot(luminance).max(0.0) * sin_theta)
+            })
+            .collect();
+        // A black map is sampled uniformly rather than not at all.
+        if !weights.iter().any(|&weight| weight > 0.0) {
+            weights.fill(1.0);
+        }
+
+        let mut conditional = Vec::with_capacity(height * (width + 1));
+        let mut marginal = Vec::with_capacity(height + 1);
+        marginal.push(0.0);
+        for row in weights.chunks_exact(width) {
+            let mut sum = 0.0;
+            conditional.push(0.0);
+            for weight in row {
+                sum += weight;
+                conditional.push(sum);
+            }
+            marginal.push(marginal.last().unwrap() + sum);
+        }
+
+        Self {
+            width,
+            height,
+            conditional,
+            marginal,
+        }
+    }
+
+    fn row(&self, y: usize) -> &[f32] {
+        &self.conditional[y * (self.width + 1)..(y + 1) * (self.width + 1)]
+    }
+
+    /// The density of `sample` at texel `(x, y)`, per unit area of the
+    /// `(u, v)` square.
+    fn density(&self, x: usize, y: usize) -> f32 {
+        let row = self.row(y);
+        let weight = row[x + 1] - row[x];
+        weight / self.marginal[self.height] * (self.width * self.height) as f32
+    }
+
+    fn sample(&self, r1: f32, r2: f32) -> (f32, f32) {
+        let (y, dy) = sample_cumulative(&self.marginal, r1);
+        let (x, dx) = sample_cumulative(self.row(y), r2);
+        (
+            (x as f32 + dx) / self.width as f32,
+            (y as f32 + dy) / self.height as f32,
+        )
+    }
+}
+
+/// Picks a bin of the cumulative weights `cdf` for `r` in `0..1`, along with
+/// the position within the bin.
+fn sample_cumulative(cdf: &[f32], r: f32) -> (usize, f32) {
+    let bins = cdf.len() - 1;
+    let target = r * cdf[bins];
+    let index = cdf[1..].partition_point(|&sum| sum <= target).min(bins - 1);
+    let width = cdf[index + 1] - cdf[index];
+    let offset = if width > 0.0 {
+        (target - cdf[index]) / width
+    } else {
+        0.5
+    };
+
+    (index, offset.clamp(0.0, 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLES: usize = 40000;
+
+    /// A dim map with a bright spot and a colored band.
+    fn map() -> EnvironmentMap {
+        let (width, height) = (16, 8);
+        let pixels = (0..width * height)
+            .map(|index| match (index % width, index / width) {
+                (3, 2) => Vec3::new(4.0, 3.0, 2.0),
+                (_, 5) => Vec3::new(0.1, 0.8, 0.2),
+                (x, _) => Vec3::splat(0.2 + x as f32 * 0.02),
+            })
+            .collect();
+        EnvironmentMap::new(width, height, pixels).with_rotation(0.7)
+    }
+
+    fn mean(estimate: impl Fn() -> f32) -> f32 {
+        (0..SAMPLES).map(|_| estimate()).sum::<f32>() / SAMPLES as f32
+    }
+
+    #[test]
+    fn samples_with_a_density_covering_the_sphere() {
+        let map = map();
+        let solid_angle = mean(|| 1.0 / map.pdf(map.sample()));
+        assert!((solid_angle / (4.0 * PI) - 1.0).abs() < 0.02, "{}", solid_angle);
+    }
+
+    #[test]
+    fn samples_in_proportion_to_the_radiance() {
+        let map = map();
+        let luminance = |direction: Vec3| map.radiance(direction).dot(Vec3::ONE);
+        let estimate = mean(|| {
+            let direction = map.sample();
+            luminance(direction) / map.pdf(direction)
+        });
+
+        // Midpoint quadrature over the angles of the sphere.
+        let (columns, rows) = (512, 256);
+        let (d_phi, d_theta) = (2.0 * PI / columns as f32, PI / rows as f32);
+        let integral = (0..rows * columns)
+            .map(|index| {
+                let theta = ((index / columns) as f32 + 0.5) * d_theta;
+                let phi = ((index % columns) as f32 + 0.5) * d_phi;
+                let direction = Vec3::new(
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                    theta.sin() * phi.cos(),
+                );
+                luminance(direction) * theta.sin() * d_theta * d_phi
+            })
+            .sum::<f32>();
+        assert!((estimate / integral - 1.0).abs() < 0.02, "{} != {}", estimate, integral);
+    }
+}
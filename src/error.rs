@@ -0,0 +1,125 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum Error {
+    Io {
+        path: PathBuf,
+        format: &'static str,
+        source: io::Error,
+    },
+    Write {
+        path: PathBuf,
+        format: &'static str,
+        source: io::Error,
+    },
+    Image {
+        path: PathBuf,
+        format: &'static str,
+        source: image::ImageError,
+    },
+    Parse {
+        path: PathBuf,
+        format: &'static str,
+        message: String,
+    },
+    /// Data built in memory rather than read from a file.
+    Invalid {
+        format: &'static str,
+        message: String,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn io(path: &Path, format: &'static str, source: io::Error) -> Self {
+        Error::Io {
+            path: path.to_path_buf(),
+            format,
+            source,
+        }
+    }
+
+    pub fn write(path: &Path, format: &'static str, source: io::Error) -> Self {
+        Error::Write {
+            path: path.to_path_buf(),
+            format,
+            source,
+        }
+    }
+
+    pub fn image(path: &Path, format: &'static str, source: image::ImageError) -> Self {
+        Error::Image {
+            path: path.to_path_buf(),
+            format,
+            source,
+        }
+    }
+
+    pub fn parse(path: &Path, format: &'static str, message: impl Into<String>) -> Self {
+        Error::Parse {
+            path: path.to_path_buf(),
+            format,
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid(format: &'static str, message: impl Into<String>) -> Self {
+        Error::Invalid {
+            format,
+            message: message.into(),
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Error::Io { path, .. }
+            | Error::Write { path, .. }
+            | Error::Image { path, .. }
+            | Error::Parse { path, .. } => Some(path),
+            Error::Invalid { .. } => None,
+        }
+    }
+
+    pub fn format(&self) -> &'static str {
+        match self {
+            Error::Io { format, .. }
+            | Error::Write { format, .. }
+            | Error::Image { format, .. }
+            | Error::Parse { format, .. }
+            | Error::Invalid { format, .. } => format,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io { path, format, source } => {
+                write!(f, "could not read {} file {}: {}", format, path.display(), source)
+            }
+            Error::Write { path, format, source } => {
+                write!(f, "could not write {} file {}: {}", format, path.display(), source)
+            }
+            Error::Image { path, format, source } => {
+                write!(f, "could not decode {} {}: {}", format, path.display(), source)
+            }
+            Error::Parse { path, format, message } => {
+                write!(f, "invalid {} file {}: {}", format, path.display(), message)
+            }
+            Error::Invalid { format, message } => write!(f, "invalid {}: {}", format, message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } | Error::Write { source, .. } => Some(source),
+            Error::Image { source, .. } => Some(source),
+            Error::Parse { .. } | Error::Invalid { .. } => None,
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use rand::Rng;
+
+use crate::shape::hittable::*;
+use crate::texture::Texture;
+
+/// A homogeneous medium around the whole scene. Its density is `density` at
+/// `base_height` and falls off exponentially above it with `height_falloff`,
+/// which is zero for fog of the same thickness everywhere.
+///
+/// Collisions are sampled exactly from the optical depth along the ray and
+/// scatter with the phase function like any medium, so light sampling gives
+/// single scattering shafts of light.
+#[derive(Clone)]
+pub struct Fog {
+    pub density: f32,
+    pub height_falloff: f32,
+    pub base_height: f32,
+    albedo: Vec3,
+    phase_function: Material,
+}
+
+impl Fog {
+    pub fn new(density: f32, albedo: Vec3) -> Self {
+        Self {
+            density,
+            height_falloff: 0.0,
+            base_height: 0.0,
+            albedo,
+            phase_function: Material::isotropic_from_vec3(albedo),
+        }
+    }
+
+    pub fn with_height_falloff(mut self, height_falloff: f32, base_height: f32) -> Self {
+        self.height_falloff = height_falloff;
+        self.base_height = base_height;
+        self
+    }
+
+    /// Scatters with a Henyey–Greenstein lobe, forward for a positive
+    /// `asymmetry`, as haze does around the sun.
+    pub fn with_asymmetry(mut self, asymmetry: f32) -> Self {
+        let albedo = Texture::solid_color(self.albedo);
+        self.phase_function = Material::henyey_greenstein(albedo, asymmetry);
+        self
+    }
+
+    pub fn density_at(&self, point: Vec3) -> f32 {
+        self.density * (-self.height_falloff * (point.y - self.base_height)).exp()
+    }
+
+    /// The fraction of light left after travelling along the ray within
+    /// `interval`.
+    pub fn transmittance(&self, ray: &Ray, interval: Range<f32>) -> f32 {
+        (-self.optical_depth(ray, interval.start, interval.end)).exp()
+    }
+
+    fn optical_depth(&self, ray: &Ray, start: f32, end: f32) -> f32 {
+        if end <= start {
+            return 0.0;
+        }
+
+        let rate = self.height_falloff * ray.direction.y;
+        let initial = self.density_at(ray.at(start)) * ray.direction.length();
+        let distance = end - start;
+        if rate.abs() < 1e-6 {
+            initial * distance
+        } else {
+            initial * (1.0 - (-rate * distance).exp()) / rate
+        }
+    }
+}
+
+impl Hittable for Fog {
+    fn hits(&self, ray: &Ray, interval: Range<f32>) -> Option<HitRecord<'_>> {
+        if self.density <= 0.0 {
+            return None;
+        }
+
+        // Inverts the optical depth from the start of the interval, which is
+        // finite when the fog thins out fast enough along the ray.
+        let depth = -(1.0 - rand::thread_rng().random::<f32>()).ln();
+        let rate = self.height_falloff * ray.direction.y;
+        let initial = self.density_at(ray.at(interval.start)) * ray.direction.length();
+        let distance = if rate.abs() < 1e-6 {
+            depth / initial
+        } else {
+            let remaining = 1.0 - depth * rate / initial;
+            if remaining <= 0.0 {
+                return None;
+            }
+            -remaining.ln() / rate
+        };
+
+        let t = interval.start + distance;
+        if !(t < interval.end && t.is_finite()) {
+            return None;
+        }
+
+        let normal = Vec3::X;
+        Some(HitRecord {
+            t,
+            hit_point: ray.at(t),
+            normal,
+            geometric_normal: normal,
+            ray: *ray,
+            front_face: true,
+            material: &self.phase_function,
+            u: 0.0,
+            v: 0.0,
+            light_channels: DEFAULT_LIGHT_CHANNELS,
+        })
+    }
+}
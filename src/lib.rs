@@ -1,7 +1,13 @@
 pub mod camera;
+pub mod environment_map;
+pub mod error;
+pub mod fog;
+pub mod light;
+pub mod loader;
 pub mod material;
 pub mod scene;
 pub mod shape;
+pub mod sky;
 pub mod texture;
 pub mod ui;
 pub mod util;
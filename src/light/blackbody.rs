@@ -0,0 +1,42 @@
+use glam::Vec3;
+use crate::util;
+
+/// The color of an ideal black body glowing at `temperature` kelvin, from
+/// about 1900 for candle light to 6500 for daylight. It is scaled to a
+/// luminance of one, so multiply it by the strength of the light.
+pub fn blackbody(temperature: f32) -> Vec3 {
+    // Colder bodies barely glow in the visible range at all.
+    let temperature = temperature.max(800.0);
+    let mut xyz = Vec3::ZERO;
+    for step in 0..=80 {
+        let wavelength = 380.0 + 5.0 * step as f32;
+        xyz += planck(wavelength, temperature) * color_matching(wavelength);
+    }
+
+    (util::xyz_to_rgb(xyz) / xyz.y).max(Vec3::ZERO)
+}
+
+/// Spectral radiance at a wavelength in nanometers, up to a constant factor.
+fn planck(wavelength: f32, temperature: f32) -> f32 {
+    const SECOND_RADIATION_CONSTANT: f32 = 1.4388e7; // nm·K
+    let micrometers = wavelength / 1000.0;
+    let exponent = SECOND_RADIATION_CONSTANT / (wavelength * temperature);
+    1.0 / (micrometers.powi(5) * exponent.exp_m1())
+}
+
+/// The CIE 1931 color matching functions, from the multi-lobe fit by Wyman,
+/// Sloan and Shirley.
+fn color_matching(wavelength: f32) -> Vec3 {
+    let lobe = |mean: f32, below: f32, above: f32| {
+        let width = if wavelength < mean { below } else { above };
+        let t = (wavelength - mean) / width;
+        (-0.5 * t * t).exp()
+    };
+
+    Vec3::new(
+        1.056 * lobe(599.8, 37.9, 31.0) + 0.362 * lobe(442.0, 16.0, 26.7)
+            - 0.065 * lobe(501.1, 20.4, 26.2),
+        0.821 * lobe(568.8, 46.9, 40.5) + 0.286 * lobe(530.9, 16.3, 31.1),
+        1.217 * lobe(437.0, 11.8, 36.0) + 0.681 * lobe(459.0, 26.0, 13.8),
+    )
+}
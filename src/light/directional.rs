@@ -0,0 +1,43 @@
+use crate::shape::hittable::Hittable;
+use crate::shape::sun::Sun;
+pub use crate::light::*;
+
+#[derive(Clone)]
+pub struct DirectionalLight {
+    pub irradiance: Vec3,
+    /// The disk the light comes from, which is a single direction when its
+    /// radius is zero.
+    source: Sun,
+    pub channels: u32,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vec3, irradiance: Vec3, angular_diameter: f32) -> Self {
+        Self {
+            irradiance,
+            source: Sun::new(-direction, angular_diameter / 2.0),
+            channels: DEFAULT_LIGHT_CHANNELS,
+        }
+    }
+
+    /// The direction the light travels in.
+    pub fn direction(&self) -> Vec3 {
+        -self.source.direction
+    }
+
+    pub fn angular_diameter(&self) -> f32 {
+        2.0 * self.source.angular_radius
+    }
+}
+
+impl Illuminates for DirectionalLight {
+    fn illuminate(&self, point: Vec3) -> Option<Illumination> {
+        // A random direction on the disk is an unbiased estimate of its
+        // light, which is spread evenly over the disk.
+        Some(Illumination {
+            direction: self.source.random(&point).normalize(),
+            distance: f32::INFINITY,
+            irradiance: self.irradiance,
+        })
+    }
+}
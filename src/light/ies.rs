@@ -0,0 +1,275 @@
+use glam::Quat;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::error::{Error, Result};
+pub use crate::light::*;
+
+/// The light distribution of a real luminaire, read from an IESNA LM-63
+/// photometric file. Only type C photometry, used by nearly all
+/// architectural fixtures, is supported.
+///
+/// In the profile's own frame the nadir, the vertical angle 0, points along
+/// `-y`, the horizontal angle 0 along `+x` and 90 degrees along `-z`.
+/// Intensities are relative to the brightest direction, so the light keeps
+/// its color and strength; use `peak_candela` for absolute values.
+#[derive(Clone)]
+pub struct IesProfile {
+    photometry: Arc<Photometry>,
+    rotation: Quat,
+}
+
+struct Photometry {
+    /// Vertical angles in degrees, from the nadir.
+    vertical: Vec<f32>,
+    /// Horizontal angles in degrees.
+    horizontal: Vec<f32>,
+    /// The vertical angles for each horizontal one in turn.
+    candela: Vec<f32>,
+    peak: f32,
+}
+
+impl IesProfile {
+    pub fn load(path: &str) -> Result<Self> {
+        let path = Path::new(path);
+        let bytes = std::fs::read(path).map_err(|error| Error::io(path, "IES profile", error))?;
+        // Files from older tools are often Latin-1 in their keyword lines.
+        let text = String::from_utf8_lossy(&bytes);
+        let photometry =
+            parse(&text).map_err(|message| Error::parse(path, "IES profile", message))?;
+
+        Ok(Self {
+            photometry: Arc::new(photometry),
+            rotation: Quat::IDENTITY,
+        })
+    }
+
+    /// Turns the profile within the frame of its light, for example to aim
+    /// the wide side of an asymmetric wall washer.
+    pub fn with_rotation(mut self, rotation: Quat) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn peak_candela(&self) -> f32 {
+        self.photometry.peak
+    }
+
+    /// The relative intensity towards `direction`, with the nadir of the
+    /// profile turned to point along `nadir`.
+    pub fn intensity(&self, direction: Vec3, nadir: Vec3) -> f32 {
+        let frame = Quat::from_rotation_arc(Vec3::NEG_Y, nadir.normalize()) * self.rotation;
+        let local = (frame.inverse() * direction).normalize();
+
+        let vertical = (-local.y).clamp(-1.0, 1.0).acos().to_degrees();
+        let horizontal = (-local.z).atan2(local.x).to_degrees().rem_euclid(360.0);
+        self.photometry.candela(vertical, horizontal) / self.photometry.peak
+    }
+}
+
+impl Photometry {
+    fn candela(&self, vertical: f32, horizontal: f32) -> f32 {
+        let Some((v, v_fraction)) = locate(&self.vertical, vertical) else {
+            return 0.0;
+        };
+
+        // The last horizontal angle tells which symmetry the file relies on
+        // to leave out the rest of the circle.
+        let last = *self.horizontal.last().unwrap();
+        let horizontal = if self.horizontal.len() == 1 {
+            last
+        } else if last <= 90.0 {
+            let folded = horizontal % 180.0;
+            folded.min(180.0 - folded)
+        } else if last <= 180.0 {
+            horizontal.min(360.0 - horizontal)
+        } else {
+            horizontal
+        };
+
+        let column = |h: usize| {
+            let values = &self.candela[h * self.vertical.len()..];
+            let next = (v + 1).min(self.vertical.len() - 1);
+            values[v] + (values[next] - values[v]) * v_fraction
+        };
+
+        match locate(&self.horizontal, horizontal) {
+            Some((h, h_fraction)) => {
+                let next = (h + 1).min(self.horizontal.len() - 1);
+                column(h) + (column(next) - column(h)) * h_fraction
+            }
+            // A full circle without a closing column at 360 degrees wraps
+            // around to the first one.
+            None => {
+                let last = self.horizontal.len() - 1;
+                let span = self.horizontal[0] + 360.0 - self.horizontal[last];
+                let fraction = (horizontal - self.horizontal[last]).rem_euclid(360.0) / span;
+                column(last) + (column(0) - column(last)) * fraction.clamp(0.0, 1.0)
+            }
+        }
+    }
+}
+
+/// The interval of the sorted `angles` holding `angle` and how far into it
+/// `angle` lies, or `None` outside of them.
+fn locate(angles: &[f32], angle: f32) -> Option<(usize, f32)> {
+    let (first, last) = (angles[0], *angles.last().unwrap());
+    if angle < first || angle > last {
+        return None;
+    }
+    if angles.len() == 1 {
+        return Some((0, 0.0));
+    }
+
+    let index = angles[1..].partition_point(|&end| end < angle).min(angles.len() - 2);
+    let width = angles[index + 1] - angles[index];
+    let fraction = if width > 0.0 {
+        (angle - angles[index]) / width
+    } else {
+        0.0
+    };
+
+    Some((index, fraction))
+}
+
+fn parse(text: &str) -> std::result::Result<Photometry, String> {
+    let mut lines = text.lines();
+    let tilt = lines
+        .by_ref()
+        .map(str::trim)
+        .find_map(|line| line.strip_prefix("TILT="))
+        .ok_or("missing TILT line")?;
+
+    let mut numbers = lines.flat_map(|line| line.split([' ', '\t', ',']));
+    let mut next = || -> std::result::Result<f32, String> {
+        let token = numbers
+            .by_ref()
+            .find(|token| !token.is_empty())
+            .ok_or("unexpected end of file")?;
+        token
+            .parse::<f32>()
+            .map_err(|_| format!("invalid number '{token}'"))
+    };
+
+    // Tilt only matters for lamps whose output depends on how the fixture
+    // is mounted, and it is skipped.
+    if tilt.trim() == "INCLUDE" {
+        next()?;
+        let count = next()? as usize;
+        for _ in 0..2 * count {
+            next()?;
+        }
+    }
+
+    let _lamps = next()?;
+    let _lumens_per_lamp = next()?;
+    let multiplier = next()?;
+    let vertical_count = next()? as usize;
+    let horizontal_count = next()? as usize;
+    let photometric_type = next()?;
+    let _units = next()?;
+    let _dimensions = [next()?, next()?, next()?];
+    let ballast_factor = next()?;
+    let ballast_lamp_factor = next()?;
+    let _input_watts = next()?;
+
+    if photometric_type != 1.0 {
+        return Err(format!(
+            "photometric type {photometric_type} is not supported, only type C (1)"
+        ));
+    }
+    if vertical_count == 0 || horizontal_count == 0 {
+        return Err("no angles".to_string());
+    }
+
+    let mut values = |count: usize| {
+        (0..count)
+            .map(|_| next())
+            .collect::<std::result::Result<Vec<_>, _>>()
+    };
+    let vertical = values(vertical_count)?;
+    let horizontal = values(horizontal_count)?;
+    let scale = multiplier * ballast_factor * ballast_lamp_factor;
+    let candela: Vec<f32> = values(vertical_count * horizontal_count)?
+        .into_iter()
+        .map(|value| value * scale)
+        .collect();
+
+    let peak = candela.iter().copied().fold(0.0, f32::max);
+    if peak <= 0.0 {
+        return Err("no light in any direction".to_string());
+    }
+
+    Ok(Photometry {
+        vertical,
+        horizontal,
+        candela,
+        peak,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A type C profile with one vertical angle, the nadir, lit as
+    /// `candela` at each of the `horizontal` angles.
+    fn photometry(horizontal: &[f32], candela: &[f32]) -> Photometry {
+        let join = |values: &[f32]| {
+            values.iter().map(|value| format!("{value} 0")).collect::<Vec<_>>().join(" ")
+        };
+        let text = format!(
+            "IESNA:LM-63-2002\nTILT=NONE\n1 -1 1 2 {} 1 1 0 0 0\n1 1 0\n0 90\n{}\n{}\n",
+            horizontal.len(),
+            horizontal.iter().map(f32::to_string).collect::<Vec<_>>().join(" "),
+            join(candela),
+        );
+        parse(&text).unwrap()
+    }
+
+    #[test]
+    fn folds_quadrant_symmetric_profiles() {
+        let photometry = photometry(&[0.0, 90.0], &[10.0, 20.0]);
+        for (horizontal, expected) in [(45.0, 15.0), (135.0, 15.0), (180.0, 10.0), (270.0, 20.0)] {
+            assert_eq!(photometry.candela(0.0, horizontal), expected, "{horizontal}");
+        }
+    }
+
+    #[test]
+    fn folds_bilateral_profiles() {
+        let photometry = photometry(&[0.0, 90.0, 180.0], &[10.0, 20.0, 40.0]);
+        for (horizontal, expected) in [(135.0, 30.0), (225.0, 30.0), (270.0, 20.0), (315.0, 15.0)] {
+            assert_eq!(photometry.candela(0.0, horizontal), expected, "{horizontal}");
+        }
+    }
+
+    #[test]
+    fn wraps_full_profiles_around() {
+        let photometry = photometry(&[0.0, 90.0, 180.0, 270.0], &[10.0, 20.0, 30.0, 40.0]);
+        assert_eq!(photometry.candela(0.0, 225.0), 35.0);
+        assert_eq!(photometry.candela(0.0, 315.0), 25.0);
+    }
+
+    #[test]
+    fn turns_profiles_to_their_nadir() {
+        let text = "TILT=NONE\n1 -1 1 2 2 1 1 0 0 0 1 1 0\n0 90\n0 90\n8 2 8 4\n";
+        let profile = IesProfile {
+            photometry: Arc::new(parse(text).unwrap()),
+            rotation: Quat::IDENTITY,
+        };
+        assert_eq!(profile.peak_candela(), 8.0);
+
+        // Horizontally the profile is dimmer along -z than along +x.
+        assert_eq!(profile.intensity(Vec3::NEG_Y, Vec3::NEG_Y), 1.0);
+        assert_eq!(profile.intensity(Vec3::X, Vec3::NEG_Y), 0.25);
+        assert_eq!(profile.intensity(Vec3::NEG_Z, Vec3::NEG_Y), 0.5);
+        assert_eq!(profile.intensity(Vec3::Y, Vec3::NEG_Y), 0.0);
+        assert_eq!(profile.intensity(Vec3::X, Vec3::X), 1.0);
+    }
+
+    #[test]
+    fn rejects_other_photometric_types() {
+        let text = "TILT=NONE\n1 -1 1 1 1 2 1 0 0 0 1 1 0\n0\n0\n1\n";
+        assert!(matches!(parse(text), Err(message) if message.contains("type C")));
+    }
+}
@@ -0,0 +1,175 @@
+pub mod blackbody;
+pub mod directional;
+pub mod ies;
+pub mod point;
+pub mod spot;
+
+pub use glam::Vec3;
+
+pub use blackbody::blackbody;
+pub use ies::IesProfile;
+
+/// The light channel lights and objects are on unless they are told
+/// otherwise. An object is lit by a light, or by an emitting object, only
+/// when their channels overlap.
+pub const DEFAULT_LIGHT_CHANNELS: u32 = 1;
+pub const ALL_LIGHT_CHANNELS: u32 = u32::MAX;
+
+/// Lights without a surface, which can't be hit by rays and are instead
+/// reached by a shadow ray from every diffuse bounce.
+#[derive(Clone)]
+pub enum Light {
+    Point(point::PointLight),
+    Spot(spot::SpotLight),
+    Directional(directional::DirectionalLight),
+}
+
+/// The light reaching a point from one light, if nothing is in the way.
+pub struct Illumination {
+    /// Unit vector from the point towards the light.
+    pub direction: Vec3,
+    pub distance: f32,
+    /// The power arriving per unit area facing the light.
+    pub irradiance: Vec3,
+}
+
+pub trait Illuminates {
+    fn illuminate(&self, point: Vec3) -> Option<Illumination>;
+}
+
+impl Illuminates for Light {
+    fn illuminate(&self, point: Vec3) -> Option<Illumination> {
+        match self {
+            Light::Point(light) => light.illuminate(point),
+            Light::Spot(light) => light.illuminate(point),
+            Light::Directional(light) => light.illuminate(point),
+        }
+    }
+}
+
+impl Light {
+    /// `intensity` is the power per unit solid angle, in every direction.
+    pub fn point(position: Vec3, intensity: Vec3) -> Self {
+        Light::Point(point::PointLight {
+            position,
+            intensity,
+            profile: None,
+            channels: DEFAULT_LIGHT_CHANNELS,
+        })
+    }
+
+    /// A point light shining along `direction` within `outer_angle` of it,
+    /// at full intensity within `inner_angle`. Angles are in radians.
+    pub fn spot(
+        position: Vec3,
+        direction: Vec3,
+        intensity: Vec3,
+        inner_angle: f32,
+        outer_angle: f32,
+    ) -> Self {
+        Light::Spot(spot::SpotLight::new(
+            position,
+            direction,
+            intensity,
+            inner_angle,
+            outer_angle,
+        ))
+    }
+
+    /// Parallel light travelling along `direction`, as from the sun. With a
+    /// non-zero `angular_diameter` in radians it comes from a disk of that
+    /// size instead, which softens the edges of shadows.
+    pub fn directional(direction: Vec3, irradiance: Vec3, angular_diameter: f32) -> Self {
+        Light::Directional(directional::DirectionalLight::new(
+            direction,
+            irradiance,
+            angular_diameter,
+        ))
+    }
+
+    /// Shapes a point or spot light by a measured profile. Point lights aim
+    /// its nadir straight down and spot lights along their direction.
+    /// Directional lights have no position to shine from and ignore it.
+    pub fn with_profile(self, profile: IesProfile) -> Self {
+        match self {
+            Light::Point(light) => Light::Point(point::PointLight {
+                profile: Some(profile),
+                ..light
+            }),
+            Light::Spot(light) => Light::Spot(light.with_profile(profile)),
+            Light::Directional(_) => self,
+        }
+    }
+
+    /// Puts the light on the given channels, a bit mask, so it only lights
+    /// objects on at least one of them.
+    pub fn with_channels(mut self, channels: u32) -> Self {
+        match &mut self {
+            Light::Point(light) => light.channels = channels,
+            Light::Spot(light) => light.channels = channels,
+            Light::Directional(light) => light.channels = channels,
+        }
+        self
+    }
+
+    pub fn channels(&self) -> u32 {
+        match self {
+            Light::Point(light) => light.channels,
+            Light::Spot(light) => light.channels,
+            Light::Directional(light) => light.channels,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Vec3, expected: Vec3) {
+        assert!(actual.abs_diff_eq(expected, 1e-5), "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn falls_off_with_the_square_of_the_distance() {
+        let light = Light::point(Vec3::new(1.0, 2.0, 3.0), Vec3::splat(8.0));
+        let illumination = light.illuminate(Vec3::new(1.0, 2.0, -1.0)).unwrap();
+        assert_close(illumination.direction, Vec3::Z);
+        assert_eq!(illumination.distance, 4.0);
+        assert_close(illumination.irradiance, Vec3::splat(0.5));
+        assert!(light.illuminate(Vec3::new(1.0, 2.0, 3.0)).is_none());
+    }
+
+    #[test]
+    fn fades_spots_between_their_cones() {
+        let (inner, outer) = (0.2f32, 0.4f32);
+        let light = Light::spot(Vec3::Y * 4.0, Vec3::NEG_Y * 2.0, Vec3::ONE, inner, outer);
+        let at_angle = |angle: f32| Vec3::new(angle.tan() * 4.0, 0.0, 0.0);
+        let irradiance = |angle: f32| {
+            let illumination = light.illuminate(at_angle(angle))?;
+            let distance_squared = illumination.distance * illumination.distance;
+            Some(illumination.irradiance.x * distance_squared)
+        };
+
+        assert_eq!(irradiance(0.0), Some(1.0));
+        assert!((irradiance(0.15).unwrap() - 1.0).abs() < 1e-5);
+        let fraction = (0.3f32.cos() - outer.cos()) / (inner.cos() - outer.cos());
+        assert!((irradiance(0.3).unwrap() - fraction * fraction).abs() < 1e-4);
+        assert_eq!(irradiance(0.5), None);
+    }
+
+    #[test]
+    fn shines_directional_lights_from_within_their_disk() {
+        let direction = Vec3::new(1.0, -2.0, 0.5).normalize();
+        let light = Light::directional(direction, Vec3::ONE, 0.0);
+        let illumination = light.illuminate(Vec3::ZERO).unwrap();
+        assert_close(illumination.direction, -direction);
+        assert_eq!(illumination.distance, f32::INFINITY);
+
+        let light = Light::directional(direction, Vec3::ONE, 0.1);
+        for _ in 0..1000 {
+            let illumination = light.illuminate(Vec3::ZERO).unwrap();
+            assert!(illumination.direction.angle_between(-direction) <= 0.05 + 1e-4);
+            assert_close(illumination.irradiance, Vec3::ONE);
+        }
+    }
+}
@@ -0,0 +1,32 @@
+pub use crate::light::*;
+
+#[derive(Clone)]
+pub struct PointLight {
+    pub position: Vec3,
+    pub intensity: Vec3,
+    pub profile: Option<IesProfile>,
+    pub channels: u32,
+}
+
+impl Illuminates for PointLight {
+    fn illuminate(&self, point: Vec3) -> Option<Illumination> {
+        let offset = self.position - point;
+        let distance_squared = offset.length_squared();
+        if distance_squared == 0.0 {
+            return None;
+        }
+
+        let distance = distance_squared.sqrt();
+        let direction = offset / distance;
+        let profile = self
+            .profile
+            .as_ref()
+            .map_or(1.0, |profile| profile.intensity(-direction, Vec3::NEG_Y));
+
+        Some(Illumination {
+            direction,
+            distance,
+            irradiance: self.intensity * profile / distance_squared,
+        })
+    }
+}
@@ -0,0 +1,77 @@
+pub use crate::light::*;
+
+#[derive(Clone)]
+pub struct SpotLight {
+    pub position: Vec3,
+    direction: Vec3,
+    pub intensity: Vec3,
+    cos_inner: f32,
+    cos_outer: f32,
+    profile: Option<IesProfile>,
+    pub channels: u32,
+}
+
+impl SpotLight {
+    pub fn new(
+        position: Vec3,
+        direction: Vec3,
+        intensity: Vec3,
+        inner_angle: f32,
+        outer_angle: f32,
+    ) -> Self {
+        Self {
+            position,
+            direction: direction.normalize(),
+            intensity,
+            cos_inner: inner_angle.min(outer_angle).cos(),
+            cos_outer: outer_angle.cos(),
+            profile: None,
+            channels: DEFAULT_LIGHT_CHANNELS,
+        }
+    }
+
+    /// The profile is aimed with its nadir along the direction of the spot,
+    /// and the cone still limits it.
+    pub fn with_profile(mut self, profile: IesProfile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    pub fn direction(&self) -> Vec3 {
+        self.direction
+    }
+
+    /// Fades from one inside the inner cone to zero at the outer one, with
+    /// the same curve as glTF so imported scenes look as authored.
+    fn falloff(&self, cos_angle: f32) -> f32 {
+        let width = (self.cos_inner - self.cos_outer).max(1e-4);
+        let fraction = ((cos_angle - self.cos_outer) / width).clamp(0.0, 1.0);
+        fraction * fraction
+    }
+}
+
+impl Illuminates for SpotLight {
+    fn illuminate(&self, point: Vec3) -> Option<Illumination> {
+        let offset = self.position - point;
+        let distance_squared = offset.length_squared();
+        if distance_squared == 0.0 {
+            return None;
+        }
+
+        let distance = distance_squared.sqrt();
+        let direction = offset / distance;
+        let mut falloff = self.falloff(-direction.dot(self.direction));
+        if let Some(profile) = &self.profile {
+            falloff *= profile.intensity(-direction, self.direction);
+        }
+        if falloff == 0.0 {
+            return None;
+        }
+
+        Some(Illumination {
+            direction,
+            distance,
+            irradiance: self.intensity * falloff / distance_squared,
+        })
+    }
+}
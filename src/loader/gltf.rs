@@ -0,0 +1,854 @@
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+use base64::engine::DecodePaddingMode;
+use base64::Engine;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::camera::builder::CameraBuilder;
+use crate::error::{Error, Result};
+use crate::light::Light;
+use crate::material::Material;
+use crate::scene::Scene;
+use crate::shape::mesh::Mesh;
+use crate::shape::triangle_mesh::*;
+use crate::shape::Shape;
+use crate::texture::Texture;
+
+const GLB_MAGIC: &[u8] = b"glTF";
+const GLB_JSON_CHUNK: u32 = 0x4e4f_534a;
+const GLB_BIN_CHUNK: u32 = 0x004e_4942;
+
+/// Exporters differ in whether they pad data URIs, so both are accepted.
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &base64::alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+#[derive(Clone, Copy)]
+pub enum PunctualLightKind {
+    Point,
+    Spot {
+        inner_cone_angle: f32,
+        outer_cone_angle: f32,
+    },
+    Directional,
+}
+
+#[derive(Clone, Copy)]
+pub struct PunctualLight {
+    pub kind: PunctualLightKind,
+    pub color: Vec3,
+    pub intensity: f32,
+    pub range: Option<f32>,
+    pub position: Vec3,
+    pub direction: Vec3,
+}
+
+pub struct GltfScene {
+    pub mesh: Option<Mesh>,
+    pub cameras: Vec<CameraBuilder>,
+    pub lights: Vec<PunctualLight>,
+}
+
+impl GltfScene {
+    /// Adds the meshes to the world and the lights to the scene, and uses the
+    /// first camera, if any.
+    pub fn add_to(self, scene: &mut Scene) {
+        if let Some(mesh) = self.mesh {
+            scene.world.push(Shape::Mesh(mesh));
+        }
+
+        if let Some(camera) = self.cameras.into_iter().next() {
+            scene.camera = camera.build();
+        }
+
+        scene.lights.extend(self.lights.iter().map(PunctualLight::light));
+    }
+}
+
+impl PunctualLight {
+    /// The intensity of point and spot lights is in candela and that of
+    /// directional lights in lux, so both carry over as they are.
+    pub fn light(&self) -> Light {
+        let intensity = self.color * self.intensity;
+        match self.kind {
+            PunctualLightKind::Point => Light::point(self.position, intensity),
+            PunctualLightKind::Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            } => Light::spot(
+                self.position,
+                self.direction,
+                intensity,
+                inner_cone_angle,
+                outer_cone_angle,
+            ),
+            PunctualLightKind::Directional => Light::directional(self.direction, intensity, 0.0),
+        }
+    }
+}
+
+pub fn load(path: &str) -> Result<GltfScene> {
+    let path = Path::new(path);
+    let bytes = std::fs::read(path).map_err(|error| Error::io(path, "glTF", error))?;
+
+    parse(&bytes, path)
+}
+
+/// Imports a `.gltf` or `.glb` file already read into memory. `path` locates
+/// external buffers and images and names the file in errors.
+fn parse(bytes: &[u8], path: &Path) -> Result<GltfScene> {
+    let (document, binary_chunk) = if bytes.starts_with(GLB_MAGIC) {
+        parse_glb(bytes).map_err(|message| Error::parse(path, "glTF", message))?
+    } else {
+        (bytes, None)
+    };
+    let document: Value = serde_json::from_slice(document)
+        .map_err(|error| Error::parse(path, "glTF", format!("malformed JSON: {error}")))?;
+
+    Document::new(document, binary_chunk, path)?.import()
+}
+
+/// Splits a binary glTF container into its JSON and binary chunks.
+fn parse_glb(bytes: &[u8]) -> std::result::Result<(&[u8], Option<Vec<u8>>), String> {
+    let read_u32 = |offset: usize| {
+        bytes
+            .get(offset..offset + 4)
+            .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+    };
+
+    let mut json = None;
+    let mut binary = None;
+    let mut offset = 12;
+    while let (Some(length), Some(kind)) = (read_u32(offset), read_u32(offset + 4)) {
+        let chunk = bytes
+            .get(offset + 8..offset + 8 + length as usize)
+            .ok_or_else(|| format!("GLB chunk at byte {offset} is truncated"))?;
+        match kind {
+            GLB_JSON_CHUNK => json = Some(chunk),
+            GLB_BIN_CHUNK => binary = Some(chunk.to_vec()),
+            _ => {}
+        }
+        offset += 8 + length as usize;
+    }
+
+    Ok((json.ok_or("GLB file has no JSON chunk")?, binary))
+}
+
+struct Document<'a> {
+    json: Value,
+    buffers: Vec<Vec<u8>>,
+    path: &'a Path,
+    directory: &'a Path,
+    textures: HashMap<usize, Texture>,
+}
+
+impl<'a> Document<'a> {
+    fn new(json: Value, binary_chunk: Option<Vec<u8>>, path: &'a Path) -> Result<Self> {
+        let directory = path.parent().unwrap_or(Path::new(""));
+        let mut binary_chunk = binary_chunk;
+        let buffers = array(&json["buffers"])
+            .iter()
+            .map(|buffer| match buffer["uri"].as_str() {
+                Some(uri) => read_uri(uri, path),
+                None => Ok(binary_chunk.take().unwrap_or_default()),
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            json,
+            buffers,
+            path,
+            directory,
+            textures: HashMap::new(),
+        })
+    }
+
+    fn error(&self, message: impl Into<String>) -> Error {
+        Error::parse(self.path, "glTF", message)
+    }
+
+    /// The entry `index` of the top-level array `collection`, such as
+    /// `"accessors"`, or an error when the file refers past its end.
+    fn item(&self, collection: &str, index: usize) -> Result<&Value> {
+        array(&self.json[collection])
+            .get(index)
+            .ok_or_else(|| self.error(format!("{collection} index {index} is out of range")))
+    }
+
+    fn import(mut self) -> Result<GltfScene> {
+        let mut scene = GltfScene {
+            mesh: None,
+            cameras: Vec::new(),
+            lights: Vec::new(),
+        };
+
+        let mut materials: Vec<Material> = Vec::new();
+        let mut normal_maps = Vec::new();
+        let material_count = array(&self.json["materials"]).len();
+        for index in 0..material_count {
+            let (material, normal_map) = self.material(index)?;
+            materials.push(material);
+            normal_maps.push(normal_map);
+        }
+        // Primitives without a material use the glTF default material, which
+        // is a plain white surface.
+        materials.push(Material::default());
+        normal_maps.push(None);
+
+        let mut geometry = TriangleMesh::default();
+        let mut visited = vec![false; array(&self.json["nodes"]).len()];
+        // The tree is walked with a stack rather than recursion, so deeply
+        // nested nodes cannot overflow the call stack.
+        let mut stack: Vec<(usize, Mat4)> = self
+            .root_nodes()?
+            .into_iter()
+            .rev()
+            .map(|node| (node, Mat4::IDENTITY))
+            .collect();
+        while let Some((node, parent_transform)) = stack.pop() {
+            let transform = self.visit_node(node, parent_transform, &mut geometry, &mut scene)?;
+            // Nodes form a tree, so one reached twice is either shared between
+            // parents or part of a cycle.
+            if std::mem::replace(&mut visited[node], true) {
+                return Err(self.error(format!("node {node} appears more than once in the tree")));
+            }
+            let children = indices(&self.json["nodes"][node]["children"]);
+            stack.extend(children.into_iter().rev().map(|child| (child, transform)));
+        }
+
+        if geometry.triangle_count() > 0 {
+            let mut mesh = Mesh::from_geometry(geometry, materials);
+            for (index, normal_map) in normal_maps.into_iter().enumerate() {
+                if let Some(normal_map) = normal_map {
+                    mesh.set_normal_map(index as u32, normal_map);
+                }
+            }
+            for (index, material) in array(&self.json["materials"]).iter().enumerate() {
+                if let Some(name) = material["name"].as_str() {
+                    mesh.material_names[index] = name.to_string();
+                }
+            }
+            scene.mesh = Some(mesh);
+        }
+
+        Ok(scene)
+    }
+
+    fn root_nodes(&self) -> Result<Vec<usize>> {
+        if let Some(scene_index) = index(&self.json["scene"]) {
+            self.item("scenes", scene_index)?;
+        }
+        let scene = &self.json["scenes"][index(&self.json["scene"]).unwrap_or(0)];
+        if !scene.is_null() {
+            return Ok(indices(&scene["nodes"]));
+        }
+
+        let nodes = array(&self.json["nodes"]);
+        let mut is_child = vec![false; nodes.len()];
+        for child in nodes.iter().flat_map(|node| indices(&node["children"])) {
+            if let Some(is_child) = is_child.get_mut(child) {
+                *is_child = true;
+            }
+        }
+        Ok((0..nodes.len()).filter(|&index| !is_child[index]).collect())
+    }
+
+    /// Adds what node `index` holds and returns its world transform.
+    fn visit_node(
+        &self,
+        index: usize,
+        parent_transform: Mat4,
+        geometry: &mut TriangleMesh,
+        scene: &mut GltfScene,
+    ) -> Result<Mat4> {
+        let node = self.item("nodes", index)?;
+        let transform = parent_transform * node_transform(node);
+
+        if let Some(mesh) = self::index(&node["mesh"]) {
+            let material_count = array(&self.json["materials"]).len();
+            for primitive in array(&self.item("meshes", mesh)?["primitives"]) {
+                if let Some(mut primitive) = self.primitive(primitive, material_count)? {
+                    primitive.transform(&transform);
+                    // A mirroring transform turns the triangles inside out.
+                    if transform.determinant() < 0.0 {
+                        for triangle in primitive.triangles.iter_mut() {
+                            triangle.vertices.swap(1, 2);
+                        }
+                    }
+                    geometry.append(primitive);
+                }
+            }
+        }
+
+        if let Some(camera) = self::index(&node["camera"]) {
+            if let Some(camera) = self.camera(camera, &transform)? {
+                scene.cameras.push(camera);
+            }
+        }
+
+        if let Some(light) = self::index(&node["extensions"]["KHR_lights_punctual"]["light"]) {
+            if let Some(light) = self.light(light, &transform)? {
+                scene.lights.push(light);
+            }
+        }
+
+        Ok(transform)
+    }
+
+    fn primitive(&self, primitive: &Value, material_count: usize) -> Result<Option<TriangleMesh>> {
+        // Only triangle lists are supported.
+        if index(&primitive["mode"]).unwrap_or(4) != 4 {
+            return Ok(None);
+        }
+
+        let positions: Vec<Vec3> = match index(&primitive["attributes"]["POSITION"]) {
+            Some(accessor) => self.accessor(accessor)?,
+            None => return Err(self.error("primitive has no POSITION attribute")),
+        }
+        .iter()
+        .map(|value| Vec3::from_slice(value))
+        .collect();
+        let vertex_count = positions.len();
+
+        // Every other attribute must have one value per position.
+        let attribute = |name: &str| -> Result<Option<Vec<[f32; 4]>>> {
+            let Some(accessor) = index(&primitive["attributes"][name]) else {
+                return Ok(None);
+            };
+            let values = self.accessor(accessor)?;
+            if values.len() != vertex_count {
+                return Err(self.error(format!(
+                    "{name} has {} values for {vertex_count} positions",
+                    values.len()
+                )));
+            }
+            Ok(Some(values))
+        };
+
+        let indices = match index(&primitive["indices"]) {
+            Some(accessor) => self.indices(accessor)?,
+            None => (0..vertex_count as u32).collect::<Vec<_>>(),
+        };
+        if let Some(&index) = indices.iter().find(|&&index| index as usize >= vertex_count) {
+            return Err(self.error(format!(
+                "vertex index {index} is out of range for {vertex_count} positions"
+            )));
+        }
+        let material = match index(&primitive["material"]) {
+            Some(material) if material >= material_count => {
+                return Err(self.error(format!("materials index {material} is out of range")));
+            }
+            Some(material) => material as u32,
+            None => material_count as u32,
+        };
+
+        let mut mesh = TriangleMesh::new(
+            positions,
+            indices
+                .chunks_exact(3)
+                .map(|face| Triangle::new([face[0], face[1], face[2]], material))
+                .collect(),
+        );
+        mesh.normals = attribute("NORMAL")?
+            .map(|values| values.iter().map(|value| Vec3::from_slice(value)).collect());
+        mesh.uvs = attribute("TEXCOORD_0")?
+            .map(|values| values.iter().map(|value| Vec2::from_slice(value)).collect());
+        mesh.tangents = attribute("TANGENT")?
+            .map(|values| values.iter().map(|value| Vec4::from_slice(value)).collect());
+        mesh.colors = attribute("COLOR_0")?
+            .map(|values| values.iter().map(|value| Vec3::from_slice(value)).collect());
+
+        Ok(Some(mesh))
+    }
+
+    /// Reads an accessor as floats, normalizing integer components when the
+    /// accessor asks for it. Every element is padded to four components.
+    fn accessor(&self, index: usize) -> Result<Vec<[f32; 4]>> {
+        let layout = self.accessor_layout(index)?;
+        Ok((0..layout.count)
+            .map(|element| {
+                let mut value = [0.0, 0.0, 0.0, 1.0];
+                for (component, value) in value.iter_mut().enumerate().take(layout.components) {
+                    let bytes = layout.component(element, component);
+                    *value = read_component(bytes, layout.component_type, layout.normalized);
+                }
+                value
+            })
+            .collect())
+    }
+
+    fn indices(&self, index: usize) -> Result<Vec<u32>> {
+        let layout = self.accessor_layout(index)?;
+        if !matches!(layout.component_type, 5121 | 5123 | 5125) {
+            return Err(self.error(format!(
+                "index accessor {index} has component type {}",
+                layout.component_type
+            )));
+        }
+
+        Ok((0..layout.count)
+            .map(|element| match layout.component(element, 0) {
+                [byte] => *byte as u32,
+                [low, high] => u16::from_le_bytes([*low, *high]) as u32,
+                bytes => u32::from_le_bytes(bytes.try_into().unwrap()),
+            })
+            .collect())
+    }
+
+    /// Where the elements of an accessor lie, checked to be within its
+    /// buffer view and the view within its buffer.
+    fn accessor_layout(&self, index: usize) -> Result<AccessorLayout<'_>> {
+        let accessor = self.item("accessors", index)?;
+        let components = match accessor["type"].as_str() {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") => 4,
+            other => {
+                return Err(self.error(format!("accessor {index} has unsupported type {other:?}")))
+            }
+        };
+        let component_type = self::index(&accessor["componentType"]).unwrap_or(0);
+        let component_size = match component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5125 | 5126 => 4,
+            _ => {
+                return Err(self.error(format!(
+                    "accessor {index} has unknown component type {component_type}"
+                )))
+            }
+        };
+
+        // Accessors without a view are only used by sparse storage, which is
+        // not supported.
+        let view_index = self::index(&accessor["bufferView"])
+            .ok_or_else(|| self.error(format!("accessor {index} has no buffer view")))?;
+        let view = self.buffer_view(view_index)?;
+
+        let element_size = component_size * components;
+        let stride = self::index(&self.json["bufferViews"][view_index]["byteStride"])
+            .unwrap_or(element_size);
+        let offset = self::index(&accessor["byteOffset"]).unwrap_or(0);
+        let count = self::index(&accessor["count"]).unwrap_or(0);
+        let end = match count {
+            0 => Some(offset),
+            _ => (count - 1)
+                .checked_mul(stride)
+                .and_then(|last| last.checked_add(offset + element_size)),
+        };
+        if stride < element_size || end.is_none_or(|end| end > view.len()) {
+            return Err(self.error(format!(
+                "accessor {index} reads past the end of buffer view {view_index}"
+            )));
+        }
+
+        Ok(AccessorLayout {
+            buffer: &view[offset..],
+            stride,
+            count,
+            components,
+            component_size,
+            component_type,
+            normalized: accessor["normalized"].as_bool() == Some(true),
+        })
+    }
+
+    fn material(&mut self, index: usize) -> Result<(Material, Option<Texture>)> {
+        let material = self.item("materials", index)?.clone();
+        let pbr = &material["pbrMetallicRoughness"];
+        let base_color = match numbers(&pbr["baseColorFactor"]).as_slice() {
+            [r, g, b, ..] => Vec3::new(*r, *g, *b),
+            _ => Vec3::ONE,
+        };
+        let albedo = match self::index(&pbr["baseColorTexture"]["index"]) {
+            Some(texture) => self.texture(texture)?,
+            None => Texture::solid_color(base_color),
+        };
+        let metallic = number(&pbr["metallicFactor"]).unwrap_or(1.0);
+        let roughness = number(&pbr["roughnessFactor"]).unwrap_or(1.0);
+
+        let extensions = &material["extensions"];
+        let emissive_strength =
+            number(&extensions["KHR_materials_emissive_strength"]["emissiveStrength"])
+                .unwrap_or(1.0);
+        let emissive = match numbers(&material["emissiveFactor"]).as_slice() {
+            [r, g, b] => Vec3::new(*r, *g, *b) * emissive_strength,
+            _ => Vec3::ZERO,
+        };
+        let transmission =
+            number(&extensions["KHR_materials_transmission"]["transmissionFactor"])
+                .unwrap_or(0.0);
+        let ior = number(&extensions["KHR_materials_ior"]["ior"]).unwrap_or(1.5);
+
+        let normal_map = self::index(&material["normalTexture"]["index"])
+            .map(|texture| self.texture(texture))
+            .transpose()?;
+
+        let material = if emissive.max_element() > 0.0 {
+            match self::index(&material["emissiveTexture"]["index"]) {
+                Some(texture) => Material::diffuse_light_from_texture(Texture::scaled(
+                    self.texture(texture)?,
+                    emissive,
+                )),
+                None => Material::diffuse_light(emissive),
+            }
+        } else if transmission > 0.0 {
+            Material::dielectric(ior)
+        } else if metallic >= 0.5 {
+            Material::metal(albedo, roughness)
+        } else {
+            Material::lambertian(albedo)
+        };
+
+        Ok((material, normal_map))
+    }
+
+    fn texture(&mut self, index: usize) -> Result<Texture> {
+        let Some(source) = self::index(&self.item("textures", index)?["source"]) else {
+            return Ok(Texture::solid_color(Vec3::ONE));
+        };
+        if let Some(texture) = self.textures.get(&source) {
+            return Ok(texture.clone());
+        }
+
+        let image = self.item("images", source)?;
+        let decode = |bytes: &[u8]| {
+            Texture::image_tex_from_memory(bytes)
+                .map_err(|error| Error::image(self.path, "glTF texture", error))
+        };
+        let texture = match (image["uri"].as_str(), self::index(&image["bufferView"])) {
+            (Some(uri), _) if uri.starts_with("data:") => decode(&read_uri(uri, self.path)?)?,
+            (Some(uri), _) => Texture::try_image_tex(&self.directory.join(uri).to_string_lossy())?,
+            (None, Some(view)) => decode(self.buffer_view(view)?)?,
+            (None, None) => Texture::solid_color(Vec3::ONE),
+        };
+        self.textures.insert(source, texture.clone());
+
+        Ok(texture)
+    }
+
+    fn buffer_view(&self, index: usize) -> Result<&[u8]> {
+        let view = self.item("bufferViews", index)?;
+        let buffer_index = self::index(&view["buffer"]).unwrap_or(0);
+        let buffer = self.buffers.get(buffer_index).ok_or_else(|| {
+            self.error(format!("buffers index {buffer_index} is out of range"))
+        })?;
+        let offset = self::index(&view["byteOffset"]).unwrap_or(0);
+        let length = self::index(&view["byteLength"]).unwrap_or(0);
+
+        offset
+            .checked_add(length)
+            .and_then(|end| buffer.get(offset..end))
+            .ok_or_else(|| {
+                self.error(format!(
+                    "buffer view {index} lies outside of buffer {buffer_index}, which has {} bytes",
+                    buffer.len()
+                ))
+            })
+    }
+
+    fn camera(&self, index: usize, transform: &Mat4) -> Result<Option<CameraBuilder>> {
+        let perspective = &self.item("cameras", index)?["perspective"];
+        let Some(yfov) = number(&perspective["yfov"]) else {
+            return Ok(None);
+        };
+        let position = transform.transform_point3(Vec3::ZERO);
+        let forward = transform.transform_vector3(Vec3::NEG_Z);
+        let up = transform.transform_vector3(Vec3::Y).normalize();
+
+        let mut camera = CameraBuilder::default()
+            .with_position(position)
+            .looking_at(position + forward)
+            .up(up)
+            .with_vfov(yfov.to_degrees());
+        if let Some(aspect_ratio) = number(&perspective["aspectRatio"]) {
+            camera = camera.with_aspect_ratio(aspect_ratio);
+        }
+
+        Ok(Some(camera))
+    }
+
+    fn light(&self, index: usize, transform: &Mat4) -> Result<Option<PunctualLight>> {
+        let light = array(&self.json["extensions"]["KHR_lights_punctual"]["lights"])
+            .get(index)
+            .ok_or_else(|| self.error(format!("lights index {index} is out of range")))?;
+        let spot = &light["spot"];
+        let kind = match light["type"].as_str() {
+            Some("point") => PunctualLightKind::Point,
+            Some("directional") => PunctualLightKind::Directional,
+            Some("spot") => PunctualLightKind::Spot {
+                inner_cone_angle: number(&spot["innerConeAngle"]).unwrap_or(0.0),
+                outer_cone_angle: number(&spot["outerConeAngle"])
+                    .unwrap_or(std::f32::consts::FRAC_PI_4),
+            },
+            _ => return Ok(None),
+        };
+        let color = match numbers(&light["color"]).as_slice() {
+            [r, g, b] => Vec3::new(*r, *g, *b),
+            _ => Vec3::ONE,
+        };
+
+        Ok(Some(PunctualLight {
+            kind,
+            color,
+            intensity: number(&light["intensity"]).unwrap_or(1.0),
+            range: number(&light["range"]),
+            position: transform.transform_point3(Vec3::ZERO),
+            direction: transform.transform_vector3(Vec3::NEG_Z).normalize(),
+        }))
+    }
+}
+
+/// An accessor's elements, starting at the beginning of `buffer`.
+struct AccessorLayout<'a> {
+    buffer: &'a [u8],
+    stride: usize,
+    count: usize,
+    components: usize,
+    component_size: usize,
+    component_type: usize,
+    normalized: bool,
+}
+
+impl AccessorLayout<'_> {
+    fn component(&self, element: usize, component: usize) -> &[u8] {
+        let start = element * self.stride + component * self.component_size;
+        &self.buffer[start..start + self.component_size]
+    }
+}
+
+fn array(value: &Value) -> &[Value] {
+    value.as_array().map_or(&[], Vec::as_slice)
+}
+
+fn index(value: &Value) -> Option<usize> {
+    value.as_u64().and_then(|index| usize::try_from(index).ok())
+}
+
+fn indices(value: &Value) -> Vec<usize> {
+    array(value).iter().filter_map(index).collect()
+}
+
+fn number(value: &Value) -> Option<f32> {
+    value.as_f64().map(|number| number as f32)
+}
+
+fn numbers(value: &Value) -> Vec<f32> {
+    array(value).iter().filter_map(number).collect()
+}
+
+fn node_transform(node: &Value) -> Mat4 {
+    let matrix = numbers(&node["matrix"]);
+    if matrix.len() == 16 {
+        return Mat4::from_cols_slice(&matrix);
+    }
+
+    let translation = numbers(&node["translation"]);
+    let rotation = numbers(&node["rotation"]);
+    let scale = numbers(&node["scale"]);
+    Mat4::from_scale_rotation_translation(
+        if scale.len() == 3 { Vec3::from_slice(&scale) } else { Vec3::ONE },
+        if rotation.len() == 4 {
+            glam::Quat::from_slice(&rotation)
+        } else {
+            glam::Quat::IDENTITY
+        },
+        if translation.len() == 3 { Vec3::from_slice(&translation) } else { Vec3::ZERO },
+    )
+}
+
+/// Reads an embedded base64 data URI or a file relative to the glTF file at
+/// `path`.
+fn read_uri(uri: &str, path: &Path) -> Result<Vec<u8>> {
+    if let Some((_, data)) = uri.strip_prefix("data:").and_then(|uri| uri.split_once(";base64,")) {
+        return BASE64
+            .decode(data)
+            .map_err(|error| Error::parse(path, "glTF", format!("invalid data URI: {error}")));
+    }
+
+    let buffer_path = path.parent().unwrap_or(Path::new("")).join(uri);
+    std::fs::read(&buffer_path).map_err(|error| Error::io(&buffer_path, "glTF buffer", error))
+}
+
+fn read_component(bytes: &[u8], component_type: usize, normalized: bool) -> f32 {
+    match component_type {
+        5120 => {
+            let value = bytes[0] as i8 as f32;
+            if normalized { (value / 127.0).max(-1.0) } else { value }
+        }
+        5121 => {
+            let value = bytes[0] as f32;
+            if normalized { value / 255.0 } else { value }
+        }
+        5122 => {
+            let value = i16::from_le_bytes([bytes[0], bytes[1]]) as f32;
+            if normalized { (value / 32767.0).max(-1.0) } else { value }
+        }
+        5123 => {
+            let value = u16::from_le_bytes([bytes[0], bytes[1]]) as f32;
+            if normalized { value / 65535.0 } else { value }
+        }
+        5125 => u32::from_le_bytes(bytes.try_into().unwrap()) as f32,
+        _ => f32::from_le_bytes(bytes.try_into().unwrap()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A glTF document with one triangle whose buffer is embedded as a data
+    /// URI. `accessors` and `node` are spliced into the JSON as they are.
+    fn document(buffer: &[u8], byte_length: usize, accessors: &str, node: &str) -> String {
+        format!(
+            r#"{{
+                "buffers": [{{
+                    "uri": "data:application/octet-stream;base64,{}",
+                    "byteLength": {}
+                }}],
+                "bufferViews": [{{ "buffer": 0, "byteLength": {} }}],
+                "accessors": [{}],
+                "meshes": [{{ "primitives": [{{
+                    "attributes": {{ "POSITION": 0 }},
+                    "indices": 1
+                }}] }}],
+                "nodes": [{}]
+            }}"#,
+            BASE64.encode(buffer),
+            buffer.len(),
+            byte_length,
+            accessors,
+            node
+        )
+    }
+
+    /// Three positions followed by three `u16` indices.
+    fn triangle_buffer(indices: [u16; 3]) -> Vec<u8> {
+        let positions = [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+        let mut buffer: Vec<u8> = positions.iter().flat_map(|x| x.to_le_bytes()).collect();
+        buffer.extend(indices.iter().flat_map(|index| index.to_le_bytes()));
+        buffer
+    }
+
+    const ACCESSORS: &str = r#"
+        { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" },
+        { "bufferView": 0, "byteOffset": 36, "componentType": 5123, "count": 3, "type": "SCALAR" }
+    "#;
+
+    fn parse_str(source: &str) -> Result<GltfScene> {
+        parse(source.as_bytes(), Path::new("test.gltf"))
+    }
+
+    fn parse_error(source: &str) -> String {
+        match parse_str(source) {
+            Err(error @ Error::Parse { .. }) => error.to_string(),
+            Err(error) => panic!("expected a parse error, got {error}"),
+            Ok(_) => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn loads_a_triangle() {
+        let buffer = triangle_buffer([0, 1, 2]);
+        let scene = parse_str(&document(&buffer, buffer.len(), ACCESSORS, r#"{ "mesh": 0 }"#));
+        let mesh = scene.unwrap().mesh.unwrap();
+
+        assert_eq!(mesh.geometry.triangle_count(), 1);
+        assert_eq!(mesh.geometry.positions[1], Vec3::X);
+        assert_eq!(mesh.geometry.face_normal(&mesh.geometry.triangles[0]), Vec3::Z);
+    }
+
+    #[test]
+    fn mirrored_nodes_keep_their_facing() {
+        let buffer = triangle_buffer([0, 1, 2]);
+        let node = r#"{ "mesh": 0, "scale": [-1, 1, 1] }"#;
+        let scene = parse_str(&document(&buffer, buffer.len(), ACCESSORS, node));
+        let mesh = scene.unwrap().mesh.unwrap();
+
+        assert_eq!(mesh.geometry.face_normal(&mesh.geometry.triangles[0]), Vec3::Z);
+    }
+
+    #[test]
+    fn rejects_truncated_buffers() {
+        let buffer = triangle_buffer([0, 1, 2]);
+        // The buffer view claims the full length, but the indices are cut off.
+        let source = document(&buffer[..40], buffer.len(), ACCESSORS, r#"{ "mesh": 0 }"#);
+
+        assert!(parse_error(&source).contains("lies outside of buffer 0"));
+    }
+
+    #[test]
+    fn rejects_accessors_past_the_end_of_their_view() {
+        let buffer = triangle_buffer([0, 1, 2]);
+        let accessors = ACCESSORS.replace(
+            r#""count": 3, "type": "SCALAR""#,
+            r#""count": 6, "type": "SCALAR""#,
+        );
+        let source = document(&buffer, buffer.len(), &accessors, r#"{ "mesh": 0 }"#);
+
+        assert!(parse_error(&source).contains("reads past the end of buffer view"));
+    }
+
+    #[test]
+    fn rejects_out_of_range_vertex_indices() {
+        let buffer = triangle_buffer([0, 1, 3]);
+        let source = document(&buffer, buffer.len(), ACCESSORS, r#"{ "mesh": 0 }"#);
+
+        assert!(parse_error(&source).contains("vertex index 3 is out of range"));
+    }
+
+    #[test]
+    fn rejects_attributes_shorter_than_the_positions() {
+        let buffer = triangle_buffer([0, 1, 2]);
+        let source = document(&buffer, buffer.len(), ACCESSORS, r#"{ "mesh": 0 }"#).replace(
+            r#""POSITION": 0"#,
+            r#""POSITION": 0, "NORMAL": 2"#,
+        );
+        let source = source.replace(
+            r#""type": "SCALAR" }"#,
+            r#""type": "SCALAR" },
+            { "bufferView": 0, "componentType": 5126, "count": 2, "type": "VEC3" }"#,
+        );
+
+        assert!(parse_error(&source).contains("NORMAL has 2 values for 3 positions"));
+    }
+
+    #[test]
+    fn rejects_out_of_range_references() {
+        let buffer = triangle_buffer([0, 1, 2]);
+        for node in [r#"{ "mesh": 1 }"#, r#"{ "camera": 0 }"#, r#"{ "children": [5] }"#] {
+            let source = document(&buffer, buffer.len(), ACCESSORS, node);
+            assert!(parse_error(&source).contains("out of range"), "{node}");
+        }
+
+        let source = document(&buffer, buffer.len(), ACCESSORS, r#"{ "mesh": 0 }"#)
+            .replace(r#""buffer": 0"#, r#""buffer": 2"#);
+        assert!(parse_error(&source).contains("buffers index 2 is out of range"));
+    }
+
+    #[test]
+    fn rejects_node_cycles() {
+        let buffer = triangle_buffer([0, 1, 2]);
+        let nodes = r#"{ "children": [1] }, { "children": [0] }"#;
+        let source = document(&buffer, buffer.len(), ACCESSORS, nodes)
+            .replace(r#""nodes": ["#, r#""scenes": [{ "nodes": [0] }], "nodes": ["#);
+
+        assert!(parse_error(&source).contains("appears more than once"));
+    }
+
+    #[test]
+    fn rejects_malformed_containers() {
+        assert!(parse_error("{ \"nodes\": [ ").contains("malformed JSON"));
+
+        let mut glb = b"glTF".to_vec();
+        glb.extend([2, 0, 0, 0, 100, 0, 0, 0]);
+        glb.extend(64u32.to_le_bytes());
+        glb.extend(GLB_JSON_CHUNK.to_le_bytes());
+        glb.extend(b"{}");
+        let error = parse(&glb, Path::new("test.glb")).err().unwrap();
+        assert!(error.to_string().contains("truncated"));
+    }
+}
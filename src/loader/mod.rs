@@ -0,0 +1,72 @@
+pub mod gltf;
+pub mod mtl;
+pub mod obj;
+pub mod ply;
+pub mod stl;
+
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+use crate::shape::mesh_cleanup::{CleanupReport, DEFAULT_WELD_TOLERANCE};
+use crate::shape::triangle_mesh::TriangleMesh;
+
+pub struct Model {
+    pub geometry: TriangleMesh,
+    pub material_names: Vec<String>,
+    pub material_libraries: Vec<PathBuf>,
+    pub cleanup_report: CleanupReport,
+}
+
+impl Model {
+    pub fn from_geometry(geometry: TriangleMesh) -> Self {
+        Self {
+            geometry,
+            material_names: vec![String::new()],
+            material_libraries: Vec::new(),
+            cleanup_report: CleanupReport::default(),
+        }
+    }
+
+    /// Runs `TriangleMesh::cleanup` with a weld tolerance relative to the size
+    /// of the model and keeps what changed in `cleanup_report`.
+    fn cleanup(&mut self) {
+        let stats = self.geometry.stats();
+        let tolerance = (stats.max - stats.min).length() * DEFAULT_WELD_TOLERANCE;
+        self.cleanup_report = self.geometry.cleanup(tolerance);
+    }
+}
+
+/// Writes a mesh file, choosing OBJ or PLY from the extension of `path`.
+pub fn save(path: &str, geometry: &TriangleMesh, material_names: &[String]) -> Result<()> {
+    let extension = Path::new(path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+
+    match extension.as_deref() {
+        Some("ply") => ply::save(path, geometry),
+        _ => obj::save(path, geometry, material_names),
+    }
+}
+
+/// Loads a mesh file, choosing the format from its extension. Files without a
+/// known extension are read as OBJ. Vertices are welded and degenerate,
+/// duplicate and inconsistently wound triangles are fixed on import; use
+/// `load_without_cleanup` to keep the geometry exactly as stored.
+pub fn load(path: &str) -> Result<Model> {
+    let mut model = load_without_cleanup(path)?;
+    model.cleanup();
+
+    Ok(model)
+}
+
+pub fn load_without_cleanup(path: &str) -> Result<Model> {
+    let extension = Path::new(path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+
+    match extension.as_deref() {
+        Some("ply") => ply::load(path),
+        Some("stl") => stl::load(path),
+        _ => obj::load(path),
+    }
+}
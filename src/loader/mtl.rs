@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::material::Material;
+use crate::texture::*;
+
+#[derive(Clone)]
+pub struct MtlMaterial {
+    pub diffuse: Vec3,
+    pub specular: Vec3,
+    pub specular_exponent: f32,
+    /// Set by `Ni`, which files often leave out for transparent materials.
+    pub refraction_index: Option<f32>,
+    pub dissolve: f32,
+    pub emission: Vec3,
+    pub diffuse_map: Option<String>,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> Self {
+        Self {
+            diffuse: Vec3::ZERO,
+            specular: Vec3::ZERO,
+            specular_exponent: 0.0,
+            refraction_index: None,
+            dissolve: 1.0,
+            emission: Vec3::ZERO,
+            diffuse_map: None,
+        }
+    }
+}
+
+impl MtlMaterial {
+    /// Picks the closest of our materials: emitters become lights, transparent
+    /// surfaces become glass, and surfaces with a stronger specular than
+    /// diffuse color become metal with a fuzz derived from the Phong exponent.
+    /// Glass without a refraction index bends light like window glass.
+    pub fn to_material(&self) -> Result<Material> {
+        if self.emission.max_element() > 0.0 {
+            return Ok(Material::diffuse_light(self.emission));
+        }
+
+        if self.dissolve < 1.0 {
+            return Ok(Material::dielectric(self.refraction_index.unwrap_or(1.5)));
+        }
+
+        if self.specular.max_element() > self.diffuse.max_element() {
+            let fuzz = (2.0 / (self.specular_exponent + 2.0)).sqrt();
+            return Ok(Material::metal_from_vec3(self.specular, fuzz));
+        }
+
+        Ok(match &self.diffuse_map {
+            Some(path) => Material::lambertian(Texture::try_image_tex(path)?),
+            None => Material::lambertian_from_vec3(self.diffuse),
+        })
+    }
+}
+
+pub fn load(path: &Path) -> Result<HashMap<String, MtlMaterial>> {
+    let source = std::fs::read_to_string(path).map_err(|error| Error::io(path, "MTL", error))?;
+
+    parse(&source, path.parent().unwrap_or(Path::new("")))
+        .map_err(|message| Error::parse(path, "MTL", message))
+}
+
+/// Parses the contents of an MTL file. Texture paths are resolved relative to
+/// `directory`.
+pub fn parse(
+    source: &str,
+    directory: &Path,
+) -> std::result::Result<HashMap<String, MtlMaterial>, String> {
+    let mut materials = HashMap::new();
+    let mut name = None;
+    let mut material = MtlMaterial::default();
+
+    for (line_number, line) in source.lines().enumerate() {
+        let error = |message: String| format!("{} on line {}", message, line_number + 1);
+        let mut terms = line.split_whitespace();
+        let Some(keyword) = terms.next() else {
+            continue;
+        };
+        match keyword {
+            "newmtl" => {
+                if let Some(name) = name.take() {
+                    materials.insert(name, std::mem::take(&mut material));
+                }
+                name = Some(terms.collect::<Vec<_>>().join(" "));
+            }
+            "Kd" => material.diffuse = parse_color(terms).map_err(error)?,
+            "Ks" => material.specular = parse_color(terms).map_err(error)?,
+            "Ke" => material.emission = parse_color(terms).map_err(error)?,
+            "Ns" => material.specular_exponent = parse_scalar(terms).map_err(error)?,
+            "Ni" => material.refraction_index = Some(parse_scalar(terms).map_err(error)?),
+            // Dissolve may be marked as depending on the viewing angle, which
+            // is not supported.
+            "d" => {
+                let terms = terms.filter(|term| *term != "-halo");
+                material.dissolve = parse_scalar(terms).map_err(error)?;
+            }
+            "Tr" => material.dissolve = 1.0 - parse_scalar(terms).map_err(error)?,
+            // Options such as `-s 1 1 1` may precede the file name, which is
+            // always the last term.
+            "map_Kd" => {
+                let file = terms.last().ok_or_else(|| error("expected a file name".into()))?;
+                material.diffuse_map = Some(directory.join(file).to_string_lossy().into_owned());
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(name) = name {
+        materials.insert(name, material);
+    }
+
+    Ok(materials)
+}
+
+fn parse_number(term: &str) -> std::result::Result<f32, String> {
+    term.parse().map_err(|_| format!("invalid number '{}'", term))
+}
+
+fn parse_scalar<'a>(mut terms: impl Iterator<Item = &'a str>) -> std::result::Result<f32, String> {
+    match (terms.next(), terms.next()) {
+        (Some(term), None) => parse_number(term),
+        _ => Err("expected 1 number".to_string()),
+    }
+}
+
+/// Reads an RGB color, or a single value for a gray one. Colors given as CIE
+/// XYZ values or spectral curves are not supported.
+fn parse_color<'a>(terms: impl Iterator<Item = &'a str>) -> std::result::Result<Vec3, String> {
+    let terms: Vec<_> = terms.collect();
+    if let Some(kind @ ("xyz" | "spectral")) = terms.first().copied() {
+        return Err(format!("{} colors are not supported", kind));
+    }
+
+    let values = terms
+        .into_iter()
+        .map(parse_number)
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    match values.as_slice() {
+        [r, g, b] => Ok(Vec3::new(*r, *g, *b)),
+        [value] => Ok(Vec3::splat(*value)),
+        _ => Err("expected 1 or 3 numbers".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(source: &str) -> String {
+        match parse(source, Path::new("")) {
+            Err(message) => message,
+            Ok(_) => panic!("expected an error"),
+        }
+    }
+
+    #[test]
+    fn parses_materials() {
+        let source = "newmtl red\nKd 1 0 0\nNs 10\n\nnewmtl glass\nKd 0.5\nd -halo 0.2\n";
+        let materials = parse(source, Path::new("textures")).unwrap();
+
+        assert_eq!(materials["red"].diffuse, Vec3::X);
+        assert_eq!(materials["red"].specular_exponent, 10.0);
+        assert_eq!(materials["glass"].diffuse, Vec3::splat(0.5));
+        assert_eq!(materials["glass"].dissolve, 0.2);
+    }
+
+    #[test]
+    fn rejects_malformed_values() {
+        assert_eq!(parse_error("newmtl a\nd abc"), "invalid number 'abc' on line 2");
+        assert_eq!(parse_error("newmtl a\nNi"), "expected 1 number on line 2");
+        assert_eq!(parse_error("newmtl a\nKd 1 0"), "expected 1 or 3 numbers on line 2");
+        assert_eq!(
+            parse_error("Kd spectral red.rfl"),
+            "spectral colors are not supported on line 1"
+        );
+    }
+
+    #[test]
+    fn treats_transparent_materials_without_an_index_as_glass() {
+        let materials = parse("newmtl glass\nd 0.5", Path::new("")).unwrap();
+        match materials["glass"].to_material().unwrap() {
+            Material::Dielectric(dielectric) => assert_eq!(dielectric.refraction_index, 1.5),
+            _ => panic!("expected glass"),
+        }
+    }
+}
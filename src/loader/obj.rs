@@ -0,0 +1,287 @@
+use std::collections::HashMap;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::loader::Model;
+use crate::shape::triangle_mesh::*;
+
+type Corner = (usize, Option<usize>, Option<usize>);
+
+pub fn load(path: &str) -> Result<Model> {
+    let path = Path::new(path);
+    let source = std::fs::read_to_string(path).map_err(|error| Error::io(path, "OBJ", error))?;
+
+    parse(&source, path.parent().unwrap_or(Path::new("")))
+        .map_err(|message| Error::parse(path, "OBJ", message))
+}
+
+/// Writes `geometry` as an OBJ file with its normals and texture coordinates.
+/// Vertex colors are appended to the `v` lines, and faces are grouped with
+/// `usemtl` statements naming their entry in `material_names`.
+pub fn save(path: &str, geometry: &TriangleMesh, material_names: &[String]) -> Result<()> {
+    let path = Path::new(path);
+    let file = std::fs::File::create(path).map_err(|error| Error::write(path, "OBJ", error))?;
+
+    write(&mut BufWriter::new(file), geometry, material_names)
+        .map_err(|error| Error::write(path, "OBJ", error))
+}
+
+pub fn write(
+    writer: &mut impl Write,
+    geometry: &TriangleMesh,
+    material_names: &[String],
+) -> std::io::Result<()> {
+    for (index, position) in geometry.positions.iter().enumerate() {
+        write!(writer, "v {} {} {}", position.x, position.y, position.z)?;
+        if let Some(colors) = &geometry.colors {
+            let color = colors[index];
+            write!(writer, " {} {} {}", color.x, color.y, color.z)?;
+        }
+        writeln!(writer)?;
+    }
+    for uv in geometry.uvs.iter().flatten() {
+        writeln!(writer, "vt {} {}", uv.x, 1.0 - uv.y)?;
+    }
+    for normal in geometry.normals.iter().flatten() {
+        writeln!(writer, "vn {} {} {}", normal.x, normal.y, normal.z)?;
+    }
+
+    // Faces before the first `usemtl` use material 0, so a leading unnamed
+    // material needs no statement.
+    let mut current_material = Some(0).filter(|_| {
+        material_names.first().is_none_or(String::is_empty)
+    });
+    for triangle in &geometry.triangles {
+        if current_material != Some(triangle.material) {
+            current_material = Some(triangle.material);
+            match material_names.get(triangle.material as usize) {
+                Some(name) if !name.is_empty() => writeln!(writer, "usemtl {}", name)?,
+                _ => writeln!(writer, "usemtl material_{}", triangle.material)?,
+            }
+        }
+
+        write!(writer, "f")?;
+        for index in triangle.vertices.map(|index| index + 1) {
+            match (geometry.uvs.is_some(), geometry.normals.is_some()) {
+                (true, true) => write!(writer, " {0}/{0}/{0}", index)?,
+                (true, false) => write!(writer, " {0}/{0}", index)?,
+                (false, true) => write!(writer, " {0}//{0}", index)?,
+                (false, false) => write!(writer, " {}", index)?,
+            }
+        }
+        writeln!(writer)?;
+    }
+
+    writer.flush()
+}
+
+/// Parses the contents of an OBJ file. Polygons are triangulated as fans and
+/// every `usemtl` switches the material index of the faces that follow it.
+/// `mtllib` paths are resolved relative to `directory`.
+pub fn parse(source: &str, directory: &Path) -> std::result::Result<Model, String> {
+    let mut positions = Vec::new();
+    let mut uvs = Vec::new();
+    let mut normals = Vec::new();
+    let mut material_names = vec![String::new()];
+    let mut material_libraries = Vec::new();
+    let mut current_material = 0;
+    let mut corners: Vec<Corner> = Vec::new();
+    let mut faces = Vec::new();
+
+    for (line_number, line) in source.lines().enumerate() {
+        let error = |message: String| format!("{} on line {}", message, line_number + 1);
+        let mut terms = line.split_whitespace();
+        match terms.next() {
+            Some("v") => {
+                positions.push(Vec3::from_array(parse_numbers(terms, 3).map_err(error)?));
+            }
+            Some("vt") => {
+                // OBJ texture coordinates start at the bottom of the image, while
+                // textures are sampled from the top.
+                let [u, v] = parse_numbers(terms, 1).map_err(error)?;
+                uvs.push(Vec2::new(u, 1.0 - v));
+            }
+            Some("vn") => {
+                normals.push(Vec3::from_array(parse_numbers(terms, 3).map_err(error)?));
+            }
+            Some("f") => {
+                let lengths = [positions.len(), uvs.len(), normals.len()];
+                let start = corners.len();
+                for term in terms {
+                    corners.push(parse_corner(term, lengths).map_err(error)?);
+                }
+                (start + 1..corners.len().saturating_sub(1))
+                    .for_each(|i| faces.push(([start, i, i + 1], current_material)));
+            }
+            Some("usemtl") => {
+                let name = terms.collect::<Vec<_>>().join(" ");
+                current_material = match material_names.iter().position(|n| *n == name) {
+                    Some(index) => index,
+                    None => {
+                        material_names.push(name);
+                        material_names.len() - 1
+                    }
+                };
+            }
+            Some("mtllib") => {
+                material_libraries.extend(terms.map(|library| directory.join(library)));
+            }
+            _ => {}
+        }
+    }
+
+    let has_uvs = corners.iter().any(|(_, uv, _)| uv.is_some());
+    let has_normals = corners.iter().any(|(_, _, normal)| normal.is_some());
+    let mut geometry = TriangleMesh::default();
+    let mut mesh_uvs = Vec::new();
+    let mut mesh_normals = Vec::new();
+    let mut vertex_indices = HashMap::new();
+
+    // OBJ indexes positions, uvs and normals separately, so every distinct
+    // combination becomes one vertex of the mesh.
+    geometry.triangles = faces
+        .into_iter()
+        .map(|(face, material)| {
+            let indices = face.map(|corner| {
+                let (position, uv, normal) = corners[corner];
+                *vertex_indices.entry(corners[corner]).or_insert_with(|| {
+                    geometry.positions.push(positions[position]);
+                    if has_uvs {
+                        mesh_uvs.push(uv.map_or(Vec2::ZERO, |uv| uvs[uv]));
+                    }
+                    if has_normals {
+                        mesh_normals.push(normal.map_or(Vec3::ZERO, |normal| normals[normal]));
+                    }
+                    (geometry.positions.len() - 1) as u32
+                })
+            });
+            Triangle::new(indices, material as u32)
+        })
+        .collect();
+
+    geometry.uvs = has_uvs.then_some(mesh_uvs);
+    geometry.normals = has_normals.then_some(mesh_normals);
+
+    Ok(Model {
+        geometry,
+        material_names,
+        material_libraries,
+        cleanup_report: Default::default(),
+    })
+}
+
+/// Reads up to `N` numbers, of which the first `required` must be present.
+/// Any further terms, such as vertex colors, are ignored.
+fn parse_numbers<'a, const N: usize>(
+    mut terms: impl Iterator<Item = &'a str>,
+    required: usize,
+) -> std::result::Result<[f32; N], String> {
+    let mut values = [0.0; N];
+    for (index, value) in values.iter_mut().enumerate() {
+        match terms.next() {
+            Some(term) => {
+                *value = term
+                    .parse()
+                    .map_err(|_| format!("invalid number '{}'", term))?
+            }
+            None if index < required => return Err(format!("expected {} numbers", required)),
+            None => break,
+        }
+    }
+
+    Ok(values)
+}
+
+/// Parses a `position/uv/normal` face corner. OBJ indices are one-based and
+/// negative values count back from the most recently defined element.
+fn parse_corner(term: &str, lengths: [usize; 3]) -> std::result::Result<Corner, String> {
+    let mut terms = term.split('/');
+    let mut indices = [None; 3];
+    for ((index, length), kind) in indices
+        .iter_mut()
+        .zip(lengths)
+        .zip(["vertex", "texture coordinate", "normal"])
+    {
+        let Some(term) = terms.next().filter(|term| !term.is_empty()) else {
+            continue;
+        };
+        let value = term
+            .parse::<isize>()
+            .map_err(|_| format!("invalid {} index '{}'", kind, term))?;
+        let resolved = if value > 0 {
+            value - 1
+        } else {
+            length as isize + value
+        };
+        if !(0..length as isize).contains(&resolved) {
+            return Err(format!(
+                "{} index {} is out of range for {} defined so far",
+                kind, value, length
+            ));
+        }
+        *index = Some(resolved as usize);
+    }
+
+    let position = indices[0].ok_or_else(|| format!("face corner '{}' has no vertex", term))?;
+    Ok((position, indices[1], indices[2]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUAD: &str = "\
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 1
+vn 0 0 1
+usemtl red
+f 1/1/1 2/1/1 3/2/1 -1/2/-1
+";
+
+    fn parse_error(source: &str) -> String {
+        match parse(source, Path::new("")) {
+            Err(message) => message,
+            Ok(_) => panic!("expected an error"),
+        }
+    }
+
+    #[test]
+    fn parses_faces_with_attributes() {
+        let model = parse(QUAD, Path::new("")).unwrap();
+
+        assert_eq!(model.geometry.triangle_count(), 2);
+        assert_eq!(model.geometry.vertex_count(), 4);
+        assert_eq!(model.material_names, ["", "red"]);
+        assert_eq!(model.geometry.triangles[1].material, 1);
+        assert_eq!(model.geometry.uvs.unwrap()[2], Vec2::new(1.0, 0.0));
+        assert_eq!(model.geometry.normals.unwrap()[0], Vec3::Z);
+    }
+
+    #[test]
+    fn rejects_malformed_numbers() {
+        assert_eq!(parse_error("v 0 0 0\nv 1 x 0"), "invalid number 'x' on line 2");
+        assert_eq!(parse_error("vn 0 1"), "expected 3 numbers on line 1");
+        assert_eq!(parse_error("v 0 0 0\nf 1 1.5 1"), "invalid vertex index '1.5' on line 2");
+    }
+
+    #[test]
+    fn rejects_out_of_range_indices() {
+        assert_eq!(
+            parse_error("v 0 0 0\nf 1 -2 1"),
+            "vertex index -2 is out of range for 1 defined so far on line 2"
+        );
+        assert_eq!(
+            parse_error(&QUAD.replace("3/2/1", "3/3/1")),
+            "texture coordinate index 3 is out of range for 2 defined so far on line 9"
+        );
+        assert_eq!(
+            parse_error(&QUAD.replace("3/2/1", "3/2/0")),
+            "normal index 0 is out of range for 1 defined so far on line 9"
+        );
+    }
+}
@@ -0,0 +1,445 @@
+use std::io::{BufWriter, Write};
+
+use crate::error::{Error, Result};
+use crate::loader::Model;
+use crate::shape::triangle_mesh::*;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy)]
+enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+enum Property {
+    Scalar(String, ScalarType),
+    List(String, ScalarType, ScalarType),
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+pub fn load(path: &str) -> Result<Model> {
+    let path = std::path::Path::new(path);
+    let bytes = std::fs::read(path).map_err(|error| Error::io(path, "PLY", error))?;
+    let geometry = parse(&bytes).map_err(|message| Error::parse(path, "PLY", message))?;
+
+    Ok(Model::from_geometry(geometry))
+}
+
+/// Writes `geometry` as a binary little endian PLY file with its normals,
+/// texture coordinates and vertex colors.
+pub fn save(path: &str, geometry: &TriangleMesh) -> Result<()> {
+    let path = std::path::Path::new(path);
+    let file = std::fs::File::create(path).map_err(|error| Error::write(path, "PLY", error))?;
+
+    write(&mut BufWriter::new(file), geometry).map_err(|error| Error::write(path, "PLY", error))
+}
+
+pub fn write(writer: &mut impl Write, geometry: &TriangleMesh) -> std::io::Result<()> {
+    writeln!(writer, "ply")?;
+    writeln!(writer, "format binary_little_endian 1.0")?;
+    writeln!(writer, "element vertex {}", geometry.vertex_count())?;
+    let mut properties = vec!["x", "y", "z"];
+    if geometry.normals.is_some() {
+        properties.extend(["nx", "ny", "nz"]);
+    }
+    if geometry.uvs.is_some() {
+        properties.extend(["s", "t"]);
+    }
+    for property in properties {
+        writeln!(writer, "property float {}", property)?;
+    }
+    if geometry.colors.is_some() {
+        for property in ["red", "green", "blue"] {
+            writeln!(writer, "property uchar {}", property)?;
+        }
+    }
+    writeln!(writer, "element face {}", geometry.triangle_count())?;
+    writeln!(writer, "property list uchar uint vertex_indices")?;
+    writeln!(writer, "end_header")?;
+
+    for index in 0..geometry.vertex_count() {
+        let mut values = geometry.positions[index].to_array().to_vec();
+        if let Some(normals) = &geometry.normals {
+            values.extend(normals[index].to_array());
+        }
+        if let Some(uvs) = &geometry.uvs {
+            values.extend([uvs[index].x, 1.0 - uvs[index].y]);
+        }
+        for value in values {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        if let Some(colors) = &geometry.colors {
+            let color = (colors[index].clamp(Vec3::ZERO, Vec3::ONE) * 255.0).round();
+            writer.write_all(&color.to_array().map(|channel| channel as u8))?;
+        }
+    }
+    for triangle in &geometry.triangles {
+        writer.write_all(&[3])?;
+        for index in triangle.vertices {
+            writer.write_all(&index.to_le_bytes())?;
+        }
+    }
+
+    writer.flush()
+}
+
+/// Parses an ASCII or binary PLY file. Vertex normals, colors and texture
+/// coordinates are read when the vertex element has them, and polygons are
+/// triangulated as fans.
+pub fn parse(bytes: &[u8]) -> std::result::Result<TriangleMesh, String> {
+    if !bytes.starts_with(b"ply") {
+        return Err("missing 'ply' magic number".to_string());
+    }
+    let header_end = find(bytes, b"end_header").ok_or("header has no end_header line")?;
+    let header = std::str::from_utf8(&bytes[..header_end])
+        .map_err(|_| "header is not valid text".to_string())?;
+    let body_start = header_end
+        + bytes[header_end..]
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .ok_or("file ends after end_header")?
+        + 1;
+
+    let mut format = Format::Ascii;
+    let mut elements: Vec<Element> = Vec::new();
+    for (line_number, line) in header.lines().enumerate() {
+        let error = |message: &str| format!("{} on header line {}", message, line_number + 1);
+        let terms: Vec<&str> = line.split_whitespace().collect();
+        let scalar = |name: &str| {
+            scalar_type(name).ok_or_else(|| error(&format!("unknown property type '{}'", name)))
+        };
+        match terms.as_slice() {
+            ["format", "ascii", ..] => format = Format::Ascii,
+            ["format", "binary_little_endian", ..] => format = Format::BinaryLittleEndian,
+            ["format", "binary_big_endian", ..] => format = Format::BinaryBigEndian,
+            ["format", other, ..] => return Err(error(&format!("unknown format '{}'", other))),
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| error(&format!("invalid element count '{}'", count)))?,
+                properties: Vec::new(),
+            }),
+            ["property", rest @ ..] => {
+                let property = match rest {
+                    ["list", count_type, item_type, name] => Property::List(
+                        name.to_string(),
+                        scalar(count_type)?,
+                        scalar(item_type)?,
+                    ),
+                    [scalar_name, name] => Property::Scalar(name.to_string(), scalar(scalar_name)?),
+                    _ => return Err(error("malformed property")),
+                };
+                elements
+                    .last_mut()
+                    .ok_or_else(|| error("property before any element"))?
+                    .properties
+                    .push(property);
+            }
+            _ => {}
+        }
+    }
+
+    let vertex_count = elements
+        .iter()
+        .find(|element| element.name == "vertex")
+        .map_or(0, |element| element.count);
+    let mut reader = Reader {
+        format,
+        bytes: &bytes[body_start..],
+        position: 0,
+        line: header.lines().count() + 2,
+    };
+    let mut mesh = TriangleMesh::default();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut colors = Vec::new();
+
+    for element in &elements {
+        for index in 0..element.count {
+            let mut scalars = Vec::new();
+            let mut list = Vec::new();
+            for property in &element.properties {
+                let error = |message: String| {
+                    let name = match property {
+                        Property::Scalar(name, _) | Property::List(name, _, _) => name,
+                    };
+                    format!("{} in property '{}' of {} {}", message, name, element.name, index)
+                };
+                match property {
+                    Property::Scalar(name, scalar) => {
+                        let value = reader.read(*scalar).map_err(error)?;
+                        scalars.push((name.as_str(), value, *scalar))
+                    }
+                    Property::List(name, count_type, item_type) => {
+                        let count = reader.read(*count_type).map_err(error)?;
+                        let items = (0..count as usize)
+                            .map(|_| reader.read(*item_type))
+                            .collect::<std::result::Result<Vec<f64>, String>>()
+                            .map_err(error)?;
+                        if name == "vertex_indices" || name == "vertex_index" {
+                            list = items;
+                        }
+                    }
+                }
+            }
+
+            let value = |names: &[&str]| {
+                scalars
+                    .iter()
+                    .find(|(name, _, _)| names.contains(name))
+                    .map(|(_, value, scalar)| (*value as f32, *scalar))
+            };
+            match element.name.as_str() {
+                "vertex" => {
+                    let coordinate = |name| value(&[name]).map_or(0.0, |(value, _)| value);
+                    let position = Vec3::new(coordinate("x"), coordinate("y"), coordinate("z"));
+                    mesh.positions.push(position);
+                    if let (Some((x, _)), Some((y, _)), Some((z, _))) =
+                        (value(&["nx"]), value(&["ny"]), value(&["nz"]))
+                    {
+                        normals.push(Vec3::new(x, y, z));
+                    }
+                    if let (Some((u, _)), Some((v, _))) = (
+                        value(&["u", "s", "texture_u", "texture_s"]),
+                        value(&["v", "t", "texture_v", "texture_t"]),
+                    ) {
+                        // Like OBJ, PLY texture coordinates start at the bottom
+                        // of the image.
+                        uvs.push(Vec2::new(u, 1.0 - v));
+                    }
+                    if let (Some(red), Some(green), Some(blue)) = (
+                        value(&["red", "r", "diffuse_red"]),
+                        value(&["green", "g", "diffuse_green"]),
+                        value(&["blue", "b", "diffuse_blue"]),
+                    ) {
+                        colors.push(Vec3::new(red.0, green.0, blue.0) / color_scale(red.1));
+                    }
+                }
+                "face" => {
+                    if let Some(invalid) = list.iter().find(|&&vertex| {
+                        vertex < 0.0 || vertex.fract() != 0.0 || vertex >= vertex_count as f64
+                    }) {
+                        return Err(format!(
+                            "face {} refers to vertex {}, but there are {} vertices",
+                            index, invalid, vertex_count
+                        ));
+                    }
+                    let indices: Vec<u32> = list.iter().map(|index| *index as u32).collect();
+                    for i in 1..indices.len().saturating_sub(1) {
+                        let triangle = [indices[0], indices[i], indices[i + 1]];
+                        mesh.triangles.push(Triangle::new(triangle, 0));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let vertex_count = mesh.positions.len();
+    mesh.normals = (normals.len() == vertex_count).then_some(normals);
+    mesh.uvs = (uvs.len() == vertex_count).then_some(uvs);
+    mesh.colors = (colors.len() == vertex_count).then_some(colors);
+
+    Ok(mesh)
+}
+
+fn scalar_type(name: &str) -> Option<ScalarType> {
+    Some(match name {
+        "char" | "int8" => ScalarType::I8,
+        "uchar" | "uint8" => ScalarType::U8,
+        "short" | "int16" => ScalarType::I16,
+        "ushort" | "uint16" => ScalarType::U16,
+        "int" | "int32" => ScalarType::I32,
+        "uint" | "uint32" => ScalarType::U32,
+        "float" | "float32" => ScalarType::F32,
+        "double" | "float64" => ScalarType::F64,
+        _ => return None,
+    })
+}
+
+/// Integer colors are stored in the full range of their type, floating point
+/// colors are already in `0..1`.
+fn color_scale(scalar: ScalarType) -> f32 {
+    match scalar {
+        ScalarType::U8 | ScalarType::I8 => 255.0,
+        ScalarType::U16 | ScalarType::I16 => 65535.0,
+        _ => 1.0,
+    }
+}
+
+fn find(bytes: &[u8], pattern: &[u8]) -> Option<usize> {
+    bytes.windows(pattern.len()).position(|window| window == pattern)
+}
+
+struct Reader<'a> {
+    format: Format,
+    bytes: &'a [u8],
+    position: usize,
+    /// The line of the file being read, for ASCII data.
+    line: usize,
+}
+
+impl Reader<'_> {
+    fn read(&mut self, scalar: ScalarType) -> std::result::Result<f64, String> {
+        if self.format == Format::Ascii {
+            return self.read_ascii();
+        }
+
+        let size = match scalar {
+            ScalarType::I8 | ScalarType::U8 => 1,
+            ScalarType::I16 | ScalarType::U16 => 2,
+            ScalarType::I32 | ScalarType::U32 | ScalarType::F32 => 4,
+            ScalarType::F64 => 8,
+        };
+        let mut bytes = [0; 8];
+        let source = self
+            .bytes
+            .get(self.position..self.position + size)
+            .ok_or("unexpected end of file")?;
+        bytes[..size].copy_from_slice(source);
+        self.position += size;
+        if self.format == Format::BinaryBigEndian {
+            bytes[..size].reverse();
+        }
+
+        let [b0, b1, b2, b3, ..] = bytes;
+        Ok(match scalar {
+            ScalarType::I8 => b0 as i8 as f64,
+            ScalarType::U8 => b0 as f64,
+            ScalarType::I16 => i16::from_le_bytes([b0, b1]) as f64,
+            ScalarType::U16 => u16::from_le_bytes([b0, b1]) as f64,
+            ScalarType::I32 => i32::from_le_bytes([b0, b1, b2, b3]) as f64,
+            ScalarType::U32 => u32::from_le_bytes([b0, b1, b2, b3]) as f64,
+            ScalarType::F32 => f32::from_le_bytes([b0, b1, b2, b3]) as f64,
+            ScalarType::F64 => f64::from_le_bytes(bytes),
+        })
+    }
+
+    fn read_ascii(&mut self) -> std::result::Result<f64, String> {
+        loop {
+            match self.bytes.get(self.position) {
+                Some(b'\n') => self.line += 1,
+                Some(byte) if byte.is_ascii_whitespace() => {}
+                Some(_) => break,
+                None => return Err(format!("unexpected end of file on line {}", self.line)),
+            }
+            self.position += 1;
+        }
+        let start = self.position;
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(|byte| !byte.is_ascii_whitespace())
+        {
+            self.position += 1;
+        }
+
+        let term = String::from_utf8_lossy(&self.bytes[start..self.position]);
+        term.parse()
+            .map_err(|_| format!("invalid number '{}' on line {}", term, self.line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQUARE: &str = "\
+ply
+format ascii 1.0
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 255 0 0
+1 0 0 255 0 0
+1 1 0 255 0 0
+0 1 0 255 0 0
+4 0 1 2 3
+";
+
+    /// The square as a binary little endian file.
+    fn binary_square() -> Vec<u8> {
+        let header = SQUARE.split("end_header\n").next().unwrap();
+        let mut bytes = format!("{}end_header\n", header.replace("ascii", "binary_little_endian"))
+            .into_bytes();
+        for [x, y] in [[0.0f32, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]] {
+            for value in [x, y, 0.0] {
+                bytes.extend(value.to_le_bytes());
+            }
+            bytes.extend([255, 0, 0]);
+        }
+        bytes.push(4);
+        for index in 0..4i32 {
+            bytes.extend(index.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn parses_ascii_polygons() {
+        let mesh = parse(SQUARE.as_bytes()).unwrap();
+
+        assert_eq!(mesh.vertex_count(), 4);
+        assert_eq!(mesh.triangle_count(), 2);
+        assert_eq!(mesh.triangles[1].vertices, [0, 2, 3]);
+        assert_eq!(mesh.colors.unwrap()[3], Vec3::X);
+    }
+
+    #[test]
+    fn parses_binary_files() {
+        let mesh = parse(&binary_square()).unwrap();
+
+        assert_eq!(mesh.positions, parse(SQUARE.as_bytes()).unwrap().positions);
+        assert_eq!(mesh.triangles[1].vertices, [0, 2, 3]);
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let error = parse(SQUARE.replace("4 0 1 2 3\n", "4 0 1").as_bytes()).err().unwrap();
+        assert_eq!(
+            error,
+            "unexpected end of file on line 17 in property 'vertex_indices' of face 0"
+        );
+
+        let bytes = binary_square();
+        let error = parse(&bytes[..bytes.len() - 2]).err().unwrap();
+        assert_eq!(error, "unexpected end of file in property 'vertex_indices' of face 0");
+    }
+
+    #[test]
+    fn rejects_invalid_data() {
+        let error = parse(SQUARE.replace("1 1 0 255", "1 x 0 255").as_bytes()).err().unwrap();
+        assert_eq!(error, "invalid number 'x' on line 15 in property 'y' of vertex 2");
+
+        let error = parse(SQUARE.replace("4 0 1 2 3", "4 0 1 2 4").as_bytes()).err().unwrap();
+        assert_eq!(error, "face 0 refers to vertex 4, but there are 4 vertices");
+
+        let error = parse(SQUARE.replace("float z", "half z").as_bytes()).err().unwrap();
+        assert_eq!(error, "unknown property type 'half' on header line 6");
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use crate::error::{Error, Result};
+use crate::loader::Model;
+use crate::shape::triangle_mesh::*;
+
+const BINARY_HEADER_SIZE: usize = 84;
+const BINARY_TRIANGLE_SIZE: usize = 50;
+
+pub fn load(path: &str) -> Result<Model> {
+    let path = std::path::Path::new(path);
+    let bytes = std::fs::read(path).map_err(|error| Error::io(path, "STL", error))?;
+    let geometry = parse(&bytes).map_err(|message| Error::parse(path, "STL", message))?;
+
+    Ok(Model::from_geometry(geometry))
+}
+
+/// Parses an ASCII or binary STL file. STL stores every triangle with its own
+/// corners, so identical corners are merged into shared vertices.
+pub fn parse(bytes: &[u8]) -> std::result::Result<TriangleMesh, String> {
+    let corners = if is_binary(bytes) {
+        parse_binary(bytes)?
+    } else {
+        let source = std::str::from_utf8(bytes)
+            .map_err(|_| "file is neither binary STL nor valid text".to_string())?;
+        parse_ascii(source)?
+    };
+
+    let mut mesh = TriangleMesh::default();
+    let mut vertex_indices = HashMap::new();
+    mesh.triangles = corners
+        .chunks_exact(3)
+        .map(|corners| {
+            let indices = [corners[0], corners[1], corners[2]].map(|corner| {
+                *vertex_indices
+                    .entry(corner.to_array().map(f32::to_bits))
+                    .or_insert_with(|| {
+                        mesh.positions.push(corner);
+                        (mesh.positions.len() - 1) as u32
+                    })
+            });
+            Triangle::new(indices, 0)
+        })
+        .collect();
+
+    Ok(mesh)
+}
+
+/// Binary files may also start with `solid`, so the file size is what tells
+/// the two formats apart.
+fn is_binary(bytes: &[u8]) -> bool {
+    let Some(count) = bytes.get(80..84) else {
+        return false;
+    };
+    let count = u32::from_le_bytes(count.try_into().unwrap()) as usize;
+
+    !bytes.starts_with(b"solid")
+        || bytes.len() == BINARY_HEADER_SIZE + count * BINARY_TRIANGLE_SIZE
+}
+
+fn parse_binary(bytes: &[u8]) -> std::result::Result<Vec<Vec3>, String> {
+    let count = match bytes.get(80..84) {
+        Some(count) => u32::from_le_bytes(count.try_into().unwrap()) as usize,
+        None => return Err("file is too short for a binary STL header".to_string()),
+    };
+    let size = BINARY_HEADER_SIZE + count * BINARY_TRIANGLE_SIZE;
+    if bytes.len() < size {
+        return Err(format!(
+            "file is truncated: {} triangles need {} bytes, but there are {}",
+            count,
+            size,
+            bytes.len()
+        ));
+    }
+
+    let read_vec3 = |offset: usize| {
+        let mut values = bytes[offset..offset + 12]
+            .chunks_exact(4)
+            .map(|word| f32::from_le_bytes(word.try_into().unwrap()));
+        Vec3::new(values.next().unwrap(), values.next().unwrap(), values.next().unwrap())
+    };
+
+    Ok((0..count)
+        .flat_map(|triangle| {
+            // Each triangle stores its normal first, followed by the corners.
+            let offset = BINARY_HEADER_SIZE + triangle * BINARY_TRIANGLE_SIZE + 12;
+            (0..3).map(move |corner| offset + corner * 12)
+        })
+        .map(read_vec3)
+        .collect())
+}
+
+fn parse_ascii(source: &str) -> std::result::Result<Vec<Vec3>, String> {
+    let mut corners = Vec::new();
+    for (line_number, line) in source.lines().enumerate() {
+        let Some(line) = line.trim_start().strip_prefix("vertex") else {
+            continue;
+        };
+        let values = line
+            .split_whitespace()
+            .map(|value| value.parse::<f32>().ok())
+            .collect::<Option<Vec<f32>>>();
+        match values.as_deref() {
+            Some([x, y, z]) => corners.push(Vec3::new(*x, *y, *z)),
+            _ => return Err(format!("invalid vertex on line {}", line_number + 1)),
+        }
+    }
+
+    if corners.len() % 3 != 0 {
+        return Err(format!("{} vertices do not make whole triangles", corners.len()));
+    }
+    Ok(corners)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TETRAHEDRON: [[f32; 3]; 4] = [
+        [0.0, 0.0, 0.0],
+        [1.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [0.0, 0.0, 1.0],
+    ];
+    const FACES: [[usize; 3]; 4] = [[0, 2, 1], [0, 1, 3], [0, 3, 2], [1, 2, 3]];
+
+    fn binary() -> Vec<u8> {
+        let mut bytes = vec![0; 80];
+        bytes.extend((FACES.len() as u32).to_le_bytes());
+        for face in FACES {
+            bytes.extend([0; 12]);
+            for corner in face {
+                bytes.extend(TETRAHEDRON[corner].iter().flat_map(|x| x.to_le_bytes()));
+            }
+            bytes.extend([0; 2]);
+        }
+        bytes
+    }
+
+    fn ascii() -> String {
+        let mut source = "solid tetrahedron\n".to_string();
+        for face in FACES {
+            source += "facet normal 0 0 0\nouter loop\n";
+            for [x, y, z] in face.map(|corner| TETRAHEDRON[corner]) {
+                source += &format!("vertex {} {} {}\n", x, y, z);
+            }
+            source += "endloop\nendfacet\n";
+        }
+        source + "endsolid tetrahedron\n"
+    }
+
+    #[test]
+    fn merges_shared_corners() {
+        for mesh in [parse(&binary()).unwrap(), parse(ascii().as_bytes()).unwrap()] {
+            assert_eq!(mesh.vertex_count(), 4);
+            assert_eq!(mesh.triangle_count(), 4);
+            assert_eq!(mesh.positions[1], Vec3::Y);
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let bytes = binary();
+        let error = parse(&bytes[..bytes.len() - 10]).err().unwrap();
+        assert_eq!(error, "file is truncated: 4 triangles need 284 bytes, but there are 274");
+
+        let source = ascii();
+        let end = source.rfind("vertex").unwrap();
+        let error = parse(&source.as_bytes()[..end]).err().unwrap();
+        assert_eq!(error, "11 vertices do not make whole triangles");
+    }
+
+    #[test]
+    fn rejects_malformed_vertices() {
+        let source = ascii().replacen("vertex 1 0 0", "vertex 1 0", 1);
+        assert_eq!(parse(source.as_bytes()).err().unwrap(), "invalid vertex on line 6");
+    }
+}
@@ -13,7 +13,7 @@ impl Dielectric {
 }
 
 impl Scatters for Dielectric {
-    fn scatters(&self, hit_record: &HitRecord) -> Option<Scattered> {
+    fn scatters(&self, hit_record: &HitRecord) -> Option<Scattered<'_>> {
         let refraction_ratio = if hit_record.front_face {
             1.0 / self.refraction_index
         } else {
@@ -1,17 +1,57 @@
+use crate::light::IesProfile;
 pub use crate::material::*;
 
 #[derive(Clone)]
 pub struct DiffuseLight {
     pub emission: Texture,
+    pub strength: f32,
+    /// Emits from the back face as well as the front.
+    pub two_sided: bool,
+    pub profile: Option<IesProfile>,
+}
+
+impl DiffuseLight {
+    pub fn new(emission: Texture) -> Self {
+        Self {
+            emission,
+            strength: 1.0,
+            two_sided: false,
+            profile: None,
+        }
+    }
+
+    pub fn with_strength(mut self, strength: f32) -> Self {
+        self.strength = strength;
+        self
+    }
+
+    pub fn with_two_sided(mut self, two_sided: bool) -> Self {
+        self.two_sided = two_sided;
+        self
+    }
+
+    /// Scales the emission by a measured profile with its nadir along the
+    /// normal of the emitting face, so panels and troffers spread their
+    /// light like the real fixture.
+    pub fn with_profile(mut self, profile: IesProfile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
 }
 
 impl Scatters for DiffuseLight {
     fn emitted(&self, hit_record: &HitRecord) -> Vec3 {
-        if !hit_record.front_face {
+        if !hit_record.front_face && !self.two_sided {
             return Vec3::ZERO;
         }
 
-        self.emission
-            .get_color_value(hit_record.u, hit_record.v, hit_record.hit_point)
+        let emission = self
+            .emission
+            .get_color_value(hit_record.u, hit_record.v, hit_record.hit_point);
+        let profile = self.profile.as_ref().map_or(1.0, |profile| {
+            profile.intensity(-hit_record.ray.direction, hit_record.normal)
+        });
+
+        emission * self.strength * profile
     }
 }
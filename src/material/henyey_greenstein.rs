@@ -0,0 +1,46 @@
+pub use crate::material::*;
+pub use crate::texture::*;
+use crate::pdf::ProbabilityDensityFunction;
+
+/// An anisotropic phase function for media, a blend of a main lobe with
+/// asymmetry `asymmetry` and a second one with `second_asymmetry`, weighted
+/// by `blend`. With `blend` at one it is the single-lobe Henyey–Greenstein.
+#[derive(Clone)]
+pub struct HenyeyGreenstein {
+    pub albedo: Texture,
+    pub asymmetry: f32,
+    pub second_asymmetry: f32,
+    pub blend: f32,
+}
+
+impl HenyeyGreenstein {
+    fn pdf(&self, hit_record: &HitRecord) -> PDF<'static> {
+        PDF::henyey_greenstein(
+            &hit_record.ray.direction,
+            [
+                (self.asymmetry, self.blend),
+                (self.second_asymmetry, 1.0 - self.blend),
+            ],
+        )
+    }
+}
+
+impl Scatters for HenyeyGreenstein {
+    fn scatters(&self, hit_record: &HitRecord) -> Option<Scattered<'_>> {
+        let pdf = self.pdf(hit_record);
+
+        Some(Scattered {
+            attenuation: self.albedo.get_color_value(
+                hit_record.u,
+                hit_record.v,
+                hit_record.hit_point,
+            ),
+            scattered: Ray::with_time(hit_record.hit_point, pdf.generate(), hit_record.ray.time),
+            pdf: Some(pdf),
+        })
+    }
+
+    fn scattering_pdf(&self, _ray: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f32 {
+        self.pdf(hit_record).value(&scattered.direction)
+    }
+}
@@ -8,7 +8,7 @@ pub struct Isotropic {
 }
 
 impl Scatters for Isotropic {
-    fn scatters(&self, hit_record: &HitRecord) -> Option<Scattered> {
+    fn scatters(&self, hit_record: &HitRecord) -> Option<Scattered<'_>> {
         Some(Scattered {
             attenuation: self.albedo.get_color_value(
                 hit_record.u,
@@ -8,7 +8,7 @@ pub struct Lambertian {
 }
 
 impl Scatters for Lambertian {
-    fn scatters(&self, hit_record: &HitRecord) -> Option<Scattered> {
+    fn scatters(&self, hit_record: &HitRecord) -> Option<Scattered<'_>> {
         let scattered_direction = hit_record.normal + util::random_unit_vector();
 
         Some(Scattered {
@@ -8,7 +8,7 @@ pub struct Metal {
 }
 
 impl Scatters for Metal {
-    fn scatters(&self, hit_record: &HitRecord) -> Option<Scattered> {
+    fn scatters(&self, hit_record: &HitRecord) -> Option<Scattered<'_>> {
         let mut reflected = hit_record.ray.direction.reflect(hit_record.normal);
         reflected += self.fuzz * util::random_unit_vector();
         let scattered = Ray::with_time(
@@ -1,5 +1,6 @@
 pub mod dielectric;
 pub mod diffuse_light;
+pub mod henyey_greenstein;
 pub mod isotropic;
 pub mod lambertian;
 pub mod metal;
@@ -16,6 +17,7 @@ pub enum Material {
     Lambertian(lambertian::Lambertian),
     Dielectric(dielectric::Dielectric),
     DiffuseLight(diffuse_light::DiffuseLight),
+    HenyeyGreenstein(henyey_greenstein::HenyeyGreenstein),
 }
 
 pub struct Scattered<'a> {
@@ -25,7 +27,7 @@ pub struct Scattered<'a> {
 }
 
 pub trait Scatters {
-    fn scatters(&self, _hit_record: &HitRecord) -> Option<Scattered> {
+    fn scatters(&self, _hit_record: &HitRecord) -> Option<Scattered<'_>> {
         None
     }
     fn emitted(&self, _hit_record: &HitRecord) -> Vec3 {
@@ -37,13 +39,14 @@ pub trait Scatters {
 }
 
 impl Scatters for Material {
-    fn scatters(&self, hit_record: &HitRecord) -> Option<Scattered> {
+    fn scatters(&self, hit_record: &HitRecord) -> Option<Scattered<'_>> {
         match self {
             Material::Metal(metal) => metal.scatters(hit_record),
             Material::Isotropic(isotropic) => isotropic.scatters(hit_record),
             Material::Dielectric(dielectric) => dielectric.scatters(hit_record),
             Material::Lambertian(lambertian) => lambertian.scatters(hit_record),
             Material::DiffuseLight(diffuse_light) => diffuse_light.scatters(hit_record),
+            Material::HenyeyGreenstein(phase) => phase.scatters(hit_record),
         }
     }
 
@@ -54,6 +57,7 @@ impl Scatters for Material {
             Material::Dielectric(dielectric) => dielectric.emitted(hit_record),
             Material::Lambertian(lambertian) => lambertian.emitted(hit_record),
             Material::DiffuseLight(diffuse_light) => diffuse_light.emitted(hit_record),
+            Material::HenyeyGreenstein(phase) => phase.emitted(hit_record),
         }
     }
 
@@ -64,6 +68,7 @@ impl Scatters for Material {
             Material::Dielectric(dielectric) => dielectric.scattering_pdf(ray, hit_record, scattered),
             Material::Lambertian(lambertian) => lambertian.scattering_pdf(ray, hit_record, scattered),
             Material::DiffuseLight(diffuse_light) => diffuse_light.scattering_pdf(ray, hit_record, scattered),
+            Material::HenyeyGreenstein(phase) => phase.scattering_pdf(ray, hit_record, scattered),
         }
     }
 }
@@ -98,9 +103,11 @@ impl Material {
     }
 
     pub fn diffuse_light(emission: Vec3) -> Self {
-        Material::DiffuseLight(diffuse_light::DiffuseLight {
-            emission: Texture::solid_color(emission),
-        })
+        Material::diffuse_light_from_texture(Texture::solid_color(emission))
+    }
+
+    pub fn diffuse_light_from_texture(emission: Texture) -> Self {
+        Material::DiffuseLight(diffuse_light::DiffuseLight::new(emission))
     }
 
     pub fn isotropic_from_vec3(albedo: Vec3) -> Self {
@@ -112,6 +119,28 @@ impl Material {
     pub fn isotropic(albedo: Texture) -> Self {
         Material::Isotropic(isotropic::Isotropic { albedo })
     }
+
+    /// A phase function for media scattering forward for a positive
+    /// `asymmetry` and backward for a negative one, between -1 and 1.
+    pub fn henyey_greenstein(albedo: Texture, asymmetry: f32) -> Self {
+        Material::double_henyey_greenstein(albedo, asymmetry, 0.0, 1.0)
+    }
+
+    /// A blend of two Henyey–Greenstein lobes, typically a strong forward one
+    /// and a weaker backward one, with `blend` the weight of the first.
+    pub fn double_henyey_greenstein(
+        albedo: Texture,
+        asymmetry: f32,
+        second_asymmetry: f32,
+        blend: f32,
+    ) -> Self {
+        Material::HenyeyGreenstein(henyey_greenstein::HenyeyGreenstein {
+            albedo,
+            asymmetry: asymmetry.clamp(-0.999, 0.999),
+            second_asymmetry: second_asymmetry.clamp(-0.999, 0.999),
+            blend: blend.clamp(0.0, 1.0),
+        })
+    }
 }
 
 impl Default for Material {
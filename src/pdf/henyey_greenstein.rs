@@ -0,0 +1,112 @@
+use rand::Rng;
+use std::f32::consts::{PI, TAU};
+
+use crate::pdf::orthonormal_basis::*;
+use crate::pdf::*;
+
+/// Directions scattered by a blend of two Henyey–Greenstein lobes around the
+/// direction of travel. An asymmetry above zero favors forward scattering,
+/// below zero backward scattering, and zero is isotropic.
+pub struct HenyeyGreenstein {
+    onb: OrthonormalBasis,
+    lobes: [(f32, f32); 2],
+}
+
+impl HenyeyGreenstein {
+    /// `lobes` are pairs of asymmetry and weight, with weights summing to one.
+    pub fn new(direction: &Vec3, lobes: [(f32, f32); 2]) -> Self {
+        Self {
+            onb: OrthonormalBasis::new(direction),
+            lobes,
+        }
+    }
+}
+
+impl ProbabilityDensityFunction for HenyeyGreenstein {
+    fn value(&self, direction: &Vec3) -> f32 {
+        let cos_theta = direction.normalize().dot(self.onb.w);
+        self.lobes
+            .iter()
+            .map(|(asymmetry, weight)| weight * phase(cos_theta, *asymmetry))
+            .sum()
+    }
+
+    fn generate(&self) -> Vec3 {
+        let mut rng = rand::thread_rng();
+        let (first, second) = (self.lobes[0], self.lobes[1]);
+        let asymmetry = if rng.random::<f32>() < first.1 { first.0 } else { second.0 };
+
+        let random = rng.random::<f32>();
+        let cos_theta = if asymmetry.abs() < 1e-3 {
+            1.0 - 2.0 * random
+        } else {
+            let g_squared = asymmetry * asymmetry;
+            let square = (1.0 - g_squared) / (1.0 - asymmetry + 2.0 * asymmetry * random);
+            ((1.0 + g_squared - square * square) / (2.0 * asymmetry)).clamp(-1.0, 1.0)
+        };
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = TAU * rng.random::<f32>();
+
+        self.onb.transform(&Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta))
+    }
+}
+
+/// The Henyey–Greenstein phase function for the cosine of the angle between
+/// the directions of travel before and after scattering.
+pub fn phase(cos_theta: f32, asymmetry: f32) -> f32 {
+    let g_squared = asymmetry * asymmetry;
+    let denominator = 1.0 + g_squared - 2.0 * asymmetry * cos_theta;
+    (1.0 - g_squared) / (4.0 * PI * denominator * denominator.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLES: usize = 50000;
+    const LOBES: [(f32, f32); 2] = [(0.7, 0.8), (-0.4, 0.2)];
+
+    #[test]
+    fn generates_the_mean_cosine_of_the_lobes() {
+        let direction = Vec3::new(1.0, 2.0, 3.0);
+        let pdf = HenyeyGreenstein::new(&direction, LOBES);
+        let mean = (0..SAMPLES)
+            .map(|_| pdf.generate().dot(direction.normalize()))
+            .sum::<f32>()
+            / SAMPLES as f32;
+        assert!((mean - (0.7 * 0.8 - 0.4 * 0.2)).abs() < 0.01, "{}", mean);
+    }
+
+    #[test]
+    fn generates_directions_distributed_as_its_value() {
+        const BINS: usize = 8;
+        let direction = Vec3::new(-2.0, 0.5, 1.0);
+        let pdf = HenyeyGreenstein::new(&direction, LOBES);
+        let bin = |cos_theta: f32| (((cos_theta + 1.0) / 2.0 * BINS as f32) as usize).min(BINS - 1);
+
+        let mut counts = [0usize; BINS];
+        for _ in 0..SAMPLES {
+            let generated = pdf.generate();
+            assert!((generated.length() - 1.0).abs() < 1e-4);
+            counts[bin(generated.dot(direction.normalize()))] += 1;
+        }
+
+        // Integrate the value over each band of cosines around the direction.
+        let steps = 1000;
+        let step = 2.0 / (BINS * steps) as f32;
+        let onb = OrthonormalBasis::new(&direction);
+        for (index, count) in counts.iter().enumerate() {
+            let expected = (0..steps)
+                .map(|i| {
+                    let cos_theta = -1.0 + ((index * steps + i) as f32 + 0.5) * step;
+                    let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+                    let local = Vec3::new(sin_theta, 0.0, cos_theta);
+                    TAU * pdf.value(&onb.transform(&local)) * step
+                })
+                .sum::<f32>();
+            let fraction = *count as f32 / SAMPLES as f32;
+            let message = format!("bin {}: {} != {}", index, fraction, expected);
+            assert!((fraction - expected).abs() < 0.01, "{}", message);
+        }
+    }
+}
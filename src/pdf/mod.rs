@@ -1,6 +1,7 @@
 pub mod orthonormal_basis;
 pub mod sphere;
 pub mod cosine;
+pub mod henyey_greenstein;
 pub mod hittable;
 pub mod mixture;
 
@@ -17,6 +18,7 @@ pub enum PDF<'a> {
     Sphere(sphere::Sphere),
     Mixture(mixture::Mixture<'a>),
     Hittable(hittable::HittablePDF<'a>),
+    HenyeyGreenstein(henyey_greenstein::HenyeyGreenstein),
 }
 
 impl<'a> PDF<'a> {
@@ -28,6 +30,12 @@ impl<'a> PDF<'a> {
         PDF::Sphere(sphere::Sphere{})
     }
 
+    /// Scattering around `direction`, the direction of travel of the incoming
+    /// ray, by lobes of asymmetry and weight.
+    pub fn henyey_greenstein(direction: &Vec3, lobes: [(f32, f32); 2]) -> Self {
+        PDF::HenyeyGreenstein(henyey_greenstein::HenyeyGreenstein::new(direction, lobes))
+    }
+
     pub fn hittable(shape: &'a shape::Shape, origin: &Vec3) -> Self {
         PDF::Hittable(hittable::HittablePDF::new(shape, origin))
     }
@@ -44,6 +52,7 @@ impl<'a> ProbabilityDensityFunction for PDF<'a> {
             PDF::Sphere(sphere) => sphere.value(direction),
             PDF::Mixture(mixture) => mixture.value(direction),
             PDF::Hittable(hittable) => hittable.value(direction),
+            PDF::HenyeyGreenstein(henyey_greenstein) => henyey_greenstein.value(direction),
         }
     }
 
@@ -53,6 +62,7 @@ impl<'a> ProbabilityDensityFunction for PDF<'a> {
             PDF::Sphere(sphere) => sphere.generate(),
             PDF::Mixture(mixture) => mixture.generate(),
             PDF::Hittable(hittable) => hittable.generate(),
+            PDF::HenyeyGreenstein(henyey_greenstein) => henyey_greenstein.generate(),
         }
     }
 }
@@ -1,5 +1,6 @@
 pub use glam::f32::Vec3;
 
+#[derive(Clone)]
 pub struct OrthonormalBasis {
     pub u: Vec3,
     pub v: Vec3,
@@ -27,4 +28,9 @@ impl OrthonormalBasis {
     pub fn transform(&self, v: &Vec3) -> Vec3 {
         v.x * self.u + v.y * self.v + self.w * v.z
     }
+
+    /// The inverse of `transform`.
+    pub fn to_local(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(v.dot(self.u), v.dot(self.v), v.dot(self.w))
+    }
 }
\ No newline at end of file
@@ -4,20 +4,45 @@ use itertools::Itertools;
 use rand::Rng;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use std::ops::Div;
+use std::path::Path;
 
 use crate::camera::builder::CameraBuilder;
 use crate::camera::*;
+use crate::environment_map::EnvironmentMap;
+use crate::error::{Error, Result};
+use crate::fog::Fog;
+use crate::light::{Illuminates, Light};
 use crate::pdf::{ProbabilityDensityFunction, PDF};
 use crate::shape::*;
+use crate::sky::Sky;
 
 pub struct Scene {
     pub camera: Camera,
     pub world: Vec<Shape>,
     pub light: Option<Shape>,
+    /// Point, spot and directional lights, which are sampled with a shadow
+    /// ray at every diffuse bounce rather than found by chance.
+    pub lights: Vec<Light>,
+    /// A medium filling the space between surfaces, for aerial perspective.
+    pub fog: Option<Fog>,
     pub samples: u32,
     pub max_depth: u32,
     pub background_color: Vec3,
+    /// Shown instead of the background color, looked up at the nearest
+    /// texel.
     pub background_texture: Option<image::RgbImage>,
+    /// Shown instead of the background color or texture. Add `EnvironmentMap::light` to
+    /// `light` as well to importance sample it.
+    pub environment_map: Option<EnvironmentMap>,
+    /// An analytic daylight sky, shown instead of the background color or
+    /// texture. Its sun is only sampled when `light` includes `Sky::sun`.
+    pub sky: Option<Sky>,
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Scene {
@@ -26,10 +51,14 @@ impl Scene {
             camera: CameraBuilder::default().build(),
             world: Vec::new(),
             light: None,
+            lights: Vec::new(),
+            fog: None,
             samples: 100,
             max_depth: 50,
             background_color: Vec3::new(0.5, 0.7, 1.0),
             background_texture: None,
+            environment_map: None,
+            sky: None,
         }
     }
 
@@ -69,12 +98,32 @@ impl Scene {
             return Vec3::ZERO;
         }
 
-        match self.world.hits(&ray, 0.001..f32::INFINITY) {
+        let surface_hit = self.world.hits(&ray, 0.001..f32::INFINITY);
+        let end = surface_hit.as_ref().map_or(f32::INFINITY, |hit_record| hit_record.t);
+        let fog_hit = self.fog.as_ref().and_then(|fog| fog.hits(&ray, 0.001..end));
+
+        match fog_hit.or(surface_hit) {
             None => self.get_background(ray.direction),
             Some(hit_record) => hit_record.material.scatters(&hit_record).map_or_else(
-                || hit_record.material.emitted(&hit_record),
+                || {
+                    // Light linking: emitters only light the surfaces on
+                    // their channels.
+                    if ray.light_channels & hit_record.light_channels == 0 {
+                        return Vec3::ZERO;
+                    }
+                    hit_record.material.emitted(&hit_record)
+                },
                 |mut scattered| {
-                    match self.light.as_ref() {
+                    // Mirrors and glass pass on what reaches them rather than
+                    // being lit themselves, so they keep the channels of the ray.
+                    let (kind, light_channels) = if scattered.pdf.is_some() {
+                        (RayKind::Diffuse, hit_record.light_channels)
+                    } else {
+                        (RayKind::Specular, ray.light_channels)
+                    };
+                    scattered.scattered = scattered.scattered.with_kind(kind, light_channels);
+                    let direct = self.direct_lighting(&ray, &hit_record, &scattered);
+                    direct + match self.light.as_ref() {
                         None => scattered.attenuation * self.ray_color(scattered.scattered, depth - 1),
                         Some(_) => self.handle_light_pdf(&ray, &hit_record, &mut scattered, depth),
                     }
@@ -96,11 +145,23 @@ impl Scene {
             light, &hit_record.hit_point
         );
 
-        if let Some(ref scattered_pdf) = scattered.pdf {
-            let pdf = PDF::mixture(&light_pdf, &scattered_pdf);
+        // Light linking: lights on none of the channels of the surface are
+        // not sampled at all.
+        let linked = light.light_channels() & hit_record.light_channels != 0;
+        if let Some(scattered_pdf) = scattered.pdf.as_ref().filter(|_| linked) {
+            let pdf = PDF::mixture(&light_pdf, scattered_pdf);
+            // Directions aimed at the light are traced like the shadow rays
+            // of `lights`, so objects that cast no shadow let them through.
+            let (direction, kind) = if rand::thread_rng().random::<f32>() < 0.5 {
+                (light_pdf.generate(), RayKind::Shadow)
+            } else {
+                (scattered_pdf.generate(), RayKind::Diffuse)
+            };
+            scattered.scattered = Ray::with_time(hit_record.hit_point, direction, ray.time)
+                .with_kind(kind, hit_record.light_channels);
 
             let scattering_pdf =
-                hit_record.material.scattering_pdf(&ray, &hit_record, &scattered.scattered);
+                hit_record.material.scattering_pdf(ray, hit_record, &scattered.scattered);
             let pdf_value = pdf.value(&scattered.scattered.direction);
 
             (scattered.attenuation *
@@ -112,7 +173,57 @@ impl Scene {
         }
     }
 
+    /// The light reaching the hit straight from `lights`. Mirrors and glass
+    /// only scatter into single directions, which never point exactly at a
+    /// light, so they are skipped.
+    fn direct_lighting(&self, ray: &Ray, hit_record: &HitRecord, scattered: &Scattered) -> Vec3 {
+        if scattered.pdf.is_none() {
+            return Vec3::ZERO;
+        }
+
+        self.lights
+            .iter()
+            .filter(|light| light.channels() & hit_record.light_channels != 0)
+            .filter_map(|light| light.illuminate(hit_record.hit_point))
+            .map(|illumination| {
+                let shadow_ray =
+                    Ray::with_time(hit_record.hit_point, illumination.direction, ray.time)
+                        .with_kind(RayKind::Shadow, hit_record.light_channels);
+                let scattering_pdf =
+                    hit_record.material.scattering_pdf(ray, hit_record, &shadow_ray);
+                if scattering_pdf <= 0.0 {
+                    return Vec3::ZERO;
+                }
+
+                let transmittance = self.transmittance(&shadow_ray, illumination.distance);
+                scattered.attenuation * scattering_pdf * illumination.irradiance * transmittance
+            })
+            .sum()
+    }
+
+    /// The fraction of light left along a shadow ray, which is zero when a
+    /// surface is in the way. Heterogeneous media weaken the light along
+    /// the ray, while constant media block it at random with the probability
+    /// of a collision, which is right on average.
+    fn transmittance(&self, ray: &Ray, distance: f32) -> f32 {
+        let transmittance = self.world.transmittance(ray, 0.001..distance);
+        if transmittance == 0.0 {
+            return 0.0;
+        }
+
+        let fog = self.fog.as_ref();
+        transmittance * fog.map_or(1.0, |fog| fog.transmittance(ray, 0.001..distance))
+    }
+
     fn get_background(&self, ray_direction: Vec3) -> Vec3 {
+        if let Some(sky) = &self.sky {
+            return sky.radiance(ray_direction);
+        }
+
+        if let Some(environment_map) = &self.environment_map {
+            return environment_map.radiance(ray_direction);
+        }
+
         match &self.background_texture {
             None => self.background_color,
             Some(texture) => {
@@ -130,12 +241,33 @@ impl Scene {
     }
 
     pub fn set_background_texture(&mut self, path: &str) {
+        self.try_set_background_texture(path)
+            .unwrap_or_else(|error| panic!("{}", error));
+    }
+
+    pub fn try_set_background_texture(&mut self, path: &str) -> Result<()> {
+        let path = Path::new(path);
         let texture = ImageReader::open(path)
-            .unwrap_or_else(|_| panic!("Failed to open background texture: {}", path))
+            .map_err(|error| Error::io(path, "background texture", error))?
             .decode()
-            .unwrap_or_else(|_| panic!("Failed to decode background texture: {}", path))
+            .map_err(|error| Error::image(path, "background texture", error))?
             .into_rgb8();
 
         self.background_texture = Some(texture);
+        Ok(())
+    }
+
+    pub fn set_environment_map(&mut self, path: &str) {
+        self.try_set_environment_map(path)
+            .unwrap_or_else(|error| panic!("{}", error));
+    }
+
+    /// Loads an environment map with no rotation and unit intensity; set
+    /// `environment_map` directly for more control. Unlike a background
+    /// texture, it keeps the full range of HDR images and is filtered
+    /// bilinearly.
+    pub fn try_set_environment_map(&mut self, path: &str) -> Result<()> {
+        self.environment_map = Some(EnvironmentMap::load(path)?);
+        Ok(())
     }
 }
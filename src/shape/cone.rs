@@ -0,0 +1,119 @@
+use rand::Rng;
+use std::f32::consts::{PI, TAU};
+
+use crate::pdf::orthonormal_basis::OrthonormalBasis;
+pub use crate::shape::hittable::*;
+
+/// A cone with a base disk of `radius` around `base` and its tip at `apex`,
+/// optionally closed by the base disk.
+#[derive(Clone)]
+pub struct Cone {
+    pub base: Vec3,
+    pub height: f32,
+    pub radius: f32,
+    pub capped: bool,
+    basis: OrthonormalBasis,
+    material: Material,
+}
+
+impl Cone {
+    pub fn new(base: Vec3, apex: Vec3, radius: f32, capped: bool, material: Material) -> Self {
+        Self {
+            base,
+            height: (apex - base).length(),
+            radius,
+            capped,
+            basis: OrthonormalBasis::new(&(apex - base)),
+            material,
+        }
+    }
+
+    pub fn side_area(&self) -> f32 {
+        PI * self.radius * self.radius.hypot(self.height)
+    }
+
+    pub fn area(&self) -> f32 {
+        let cap_area = if self.capped { PI * self.radius * self.radius } else { 0.0 };
+        self.side_area() + cap_area
+    }
+}
+
+impl Hittable for Cone {
+    fn hits(&self, ray: &Ray, interval: Range<f32>) -> Option<HitRecord<'_>> {
+        let origin = self.basis.to_local(&(ray.origin - self.base));
+        let direction = self.basis.to_local(&ray.direction);
+        let mut closest: Option<(f32, Vec3, Vec3)> = None;
+        let mut consider = |t: f32, local: Vec3, normal: Vec3| {
+            if interval.contains(&t) && closest.is_none_or(|(closest_t, _, _)| t < closest_t) {
+                closest = Some((t, local, normal));
+            }
+        };
+
+        // The side is x² + y² = (k (h - z))² with k the slope of the radius.
+        let k_squared = (self.radius / self.height).powi(2);
+        let apex_distance = self.height - origin.z;
+        let a = direction.x * direction.x + direction.y * direction.y
+            - k_squared * direction.z * direction.z;
+        let half_b = origin.x * direction.x
+            + origin.y * direction.y
+            + k_squared * apex_distance * direction.z;
+        let c = origin.x * origin.x + origin.y * origin.y - k_squared * apex_distance.powi(2);
+        let roots = if a.abs() < 1e-12 {
+            (half_b.abs() > 1e-12).then(|| [-c / (2.0 * half_b); 2])
+        } else {
+            let discriminant = half_b * half_b - a * c;
+            (discriminant >= 0.0).then(|| {
+                let root = discriminant.sqrt();
+                [(-half_b - root) / a, (-half_b + root) / a]
+            })
+        };
+        for t in roots.into_iter().flatten() {
+            let local = origin + direction * t;
+            if (0.0..=self.height).contains(&local.z) {
+                let normal = Vec3::new(local.x, local.y, k_squared * (self.height - local.z));
+                consider(t, local, normal.try_normalize().unwrap_or(Vec3::Z));
+            }
+        }
+
+        if self.capped && direction.z.abs() > 1e-8 {
+            let t = -origin.z / direction.z;
+            let local = origin + direction * t;
+            if local.x * local.x + local.y * local.y <= self.radius * self.radius {
+                consider(t, local, Vec3::NEG_Z);
+            }
+        }
+
+        let (t, local, normal) = closest?;
+        let outward_normal = self.basis.transform(&normal);
+        let mut hit_record = HitRecord::new(t, ray.at(t), ray, outward_normal, &self.material);
+        let uv = if normal == Vec3::NEG_Z {
+            (0.5 + local.x / (2.0 * self.radius), 0.5 - local.y / (2.0 * self.radius))
+        } else {
+            (0.5 + local.y.atan2(local.x) / TAU, 1.0 - local.z / self.height)
+        };
+        hit_record.set_uv(uv);
+
+        Some(hit_record)
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        area_pdf_value(self, self.area(), origin, direction)
+    }
+
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        let mut rng = rand::thread_rng();
+        let angle = TAU * rng.random::<f32>();
+        // The side and the cap both have area growing with the square of the
+        // distance from their center, so both are sampled with a square root.
+        let fraction = rng.random::<f32>().sqrt();
+        let distance = self.radius * fraction;
+        let z = if rng.random::<f32>() * self.area() < self.side_area() {
+            self.height * (1.0 - fraction)
+        } else {
+            0.0
+        };
+        let local = Vec3::new(distance * angle.cos(), distance * angle.sin(), z);
+
+        (self.base + self.basis.transform(&local) - *origin).normalize()
+    }
+}
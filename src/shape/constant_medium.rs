@@ -1,72 +1,125 @@
 pub use crate::shape::hittable::*;
-use crate::shape::mesh::Mesh;
+use crate::shape::Shape;
 
+/// A medium of uniform density filling any shape. The boundary may be
+/// concave, so a ray can pass through the medium several times, and it may
+/// start inside, as with a camera in a fog-filled room.
 #[derive(Clone)]
-pub struct ConstantMedium<T: Hittable> {
-    boundary: T,
+pub struct ConstantMedium {
+    boundary: Box<Shape>,
     phase_function: Material,
     neg_inv_density: f32,
 }
-pub type SmokeCube = ConstantMedium<Mesh>;
+/// The name used when media could only fill meshes.
+pub type SmokeCube = ConstantMedium;
+/// The name used when media could only fill meshes or boxes.
+pub type SmokeBox = ConstantMedium;
 
-impl<T: Hittable> ConstantMedium<T> {
-    pub fn new(boundary: T, density: f32, albedo: Vec3) -> Self {
+impl ConstantMedium {
+    pub fn new(boundary: Shape, density: f32, albedo: Vec3) -> Self {
         Self {
-            boundary,
+            boundary: Box::new(boundary),
             phase_function: Material::isotropic_from_vec3(albedo),
             neg_inv_density: -1.0 / density,
         }
     }
+
+    /// Replaces the isotropic phase function, for example with
+    /// `Material::henyey_greenstein` for forward scattering in clouds.
+    pub fn with_phase_function(mut self, phase_function: Material) -> Self {
+        self.phase_function = phase_function;
+        self
+    }
 }
 
-impl<T: Hittable + Clone> Hittable for ConstantMedium<T> {
-    fn hits(&self, ray: &Ray, interval: Range<f32>) -> Option<HitRecord> {
-        if let Some(mut hit_record_1) = self.boundary.hits(ray, -f32::INFINITY..f32::INFINITY) {
-            if let Some(mut hit_record_2) = self
-                .boundary
-                .hits(ray, hit_record_1.t + 0.0001..f32::INFINITY)
-            {
-                if hit_record_1.t < interval.start {
-                    hit_record_1.t = interval.start;
-                }
-
-                if hit_record_2.t > interval.end {
-                    hit_record_2.t = interval.end;
-                }
-
-                if hit_record_1.t >= hit_record_2.t {
-                    return None;
-                }
-
-                if hit_record_1.t < 0.0 {
-                    hit_record_1.t = 0.0;
-                }
-
-                let distance_inside_boundary = hit_record_2.t - hit_record_1.t;
-                let hit_distance = self.neg_inv_density * rand::random::<f32>().ln();
-
-                if hit_distance > distance_inside_boundary {
-                    return None;
-                }
-
-                let t = hit_record_1.t + hit_distance;
-                let hit_point = ray.at(t);
-                let normal = Vec3::new(1.0, 0.0, 0.0);
-                let front_face = true;
-
-                return Some(HitRecord {
-                    t,
-                    hit_point,
-                    normal,
-                    ray: *ray,
-                    front_face,
-                    material: &self.phase_function,
-                    u: 0.0,
-                    v: 0.0,
-                });
+impl Hittable for ConstantMedium {
+    fn hits(&self, ray: &Ray, interval: Range<f32>) -> Option<HitRecord<'_>> {
+        let speed = ray.direction.length();
+        let mut hit_distance = self.neg_inv_density * rand::random::<f32>().ln();
+
+        // The distance to the next collision is measured through the inside
+        // of the boundary only, skipping the gaps between segments.
+        for segment in inside_segments(self.boundary.as_ref(), ray, interval) {
+            let distance_inside_boundary = (segment.end - segment.start) * speed;
+            if hit_distance > distance_inside_boundary {
+                hit_distance -= distance_inside_boundary;
+                continue;
             }
+
+            let t = segment.start + hit_distance / speed;
+            let hit_point = ray.at(t);
+            let normal = Vec3::new(1.0, 0.0, 0.0);
+            let front_face = true;
+
+            return Some(HitRecord {
+                t,
+                hit_point,
+                normal,
+                geometric_normal: normal,
+                ray: *ray,
+                front_face,
+                material: &self.phase_function,
+                u: 0.0,
+                v: 0.0,
+                light_channels: DEFAULT_LIGHT_CHANNELS,
+            });
         }
 
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLES: usize = 20000;
+
+    fn slab(start: f32, end: f32) -> Shape {
+        let (start, end) = (Vec3::new(start, -1.0, -1.0), Vec3::new(end, 1.0, 1.0));
+        Shape::cuboid(start, end, Material::default())
+    }
+
+    /// The fraction of rays colliding with `medium`, checking that the
+    /// collisions are within `inside`.
+    fn collisions(medium: &ConstantMedium, ray: &Ray, inside: impl Fn(f32) -> bool) -> f32 {
+        let count = (0..SAMPLES)
+            .filter_map(|_| medium.hits(ray, 0.0..f32::INFINITY))
+            .inspect(|hit_record| assert!(inside(ray.at(hit_record.t).x)))
+            .count();
+        count as f32 / SAMPLES as f32
+    }
+
+    fn assert_close(fraction: f32, expected: f32) {
+        assert!((fraction - expected).abs() < 0.015, "{} != {}", fraction, expected);
+    }
+
+    #[test]
+    fn collides_in_proportion_to_the_distance_inside() {
+        let medium = ConstantMedium::new(slab(-1.0, 1.0), 0.5, Vec3::ONE);
+        let inside = |x: f32| (-1.0..=1.0).contains(&x);
+
+        let from_outside = Ray::new(Vec3::X * -5.0, Vec3::X);
+        assert_close(collisions(&medium, &from_outside, inside), 1.0 - (-1.0f32).exp());
+        let from_inside = Ray::new(Vec3::ZERO, Vec3::X);
+        assert_close(collisions(&medium, &from_inside, inside), 1.0 - (-0.5f32).exp());
+    }
+
+    #[test]
+    fn skips_the_gaps_of_concave_boundaries() {
+        let boundary = Shape::list(vec![slab(-3.0, -1.0), slab(1.0, 3.0)]);
+        let medium = ConstantMedium::new(boundary, 0.5, Vec3::ONE);
+        let ray = Ray::new(Vec3::X * -5.0, Vec3::X);
+
+        let fraction = collisions(&medium, &ray, |x| x.abs() >= 1.0 && x.abs() <= 3.0);
+        assert_close(fraction, 1.0 - (-2.0f32).exp());
+    }
+
+    #[test]
+    fn measures_density_per_unit_of_length() {
+        let medium = ConstantMedium::new(slab(-1.0, 1.0), 0.5, Vec3::ONE);
+        let ray = Ray::new(Vec3::X * -5.0, Vec3::X * 4.0);
+        let fraction = collisions(&medium, &ray, |x| (-1.0..=1.0).contains(&x));
+        assert_close(fraction, 1.0 - (-1.0f32).exp());
+    }
+}
@@ -0,0 +1,169 @@
+pub use crate::shape::hittable::*;
+use crate::shape::Shape;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Operation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl Operation {
+    fn contains(self, inside_left: bool, inside_right: bool) -> bool {
+        match self {
+            Operation::Union => inside_left || inside_right,
+            Operation::Intersection => inside_left && inside_right,
+            Operation::Difference => inside_left && !inside_right,
+        }
+    }
+}
+
+/// A boolean combination of two closed shapes. The surface is found by
+/// walking every crossing of both shapes along the ray and keeping those
+/// where the ray enters or leaves the combined solid.
+#[derive(Clone)]
+pub struct Csg {
+    pub operation: Operation,
+    pub left: Box<Shape>,
+    pub right: Box<Shape>,
+}
+
+impl Csg {
+    pub fn new(operation: Operation, left: Shape, right: Shape) -> Self {
+        Self {
+            operation,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+}
+
+impl Hittable for Csg {
+    fn hits(&self, ray: &Ray, interval: Range<f32>) -> Option<HitRecord<'_>> {
+        self.hits_all(ray, interval).into_iter().next()
+    }
+
+    fn hits_all(&self, ray: &Ray, interval: Range<f32>) -> Vec<HitRecord<'_>> {
+        let left = self.left.hits_all(ray, interval.clone());
+        let right = self.right.hits_all(ray, interval.clone());
+        let mut inside_left = starts_inside(&self.left, &left, ray, &interval);
+        let mut inside_right = starts_inside(&self.right, &right, ray, &interval);
+        let mut inside = self.operation.contains(inside_left, inside_right);
+
+        let mut crossings: Vec<_> = left
+            .into_iter()
+            .map(|hit_record| (hit_record, true))
+            .chain(right.into_iter().map(|hit_record| (hit_record, false)))
+            .collect();
+        crossings.sort_by(|(a, _), (b, _)| a.t.total_cmp(&b.t));
+
+        let mut hit_records = Vec::new();
+        for (mut hit_record, from_left) in crossings {
+            if from_left {
+                inside_left = hit_record.front_face;
+            } else {
+                inside_right = hit_record.front_face;
+            }
+
+            let now_inside = self.operation.contains(inside_left, inside_right);
+            if now_inside == inside {
+                continue;
+            }
+            inside = now_inside;
+
+            // Surfaces cut out of the left shape face into the right one.
+            if self.operation == Operation::Difference && !from_left {
+                hit_record.front_face = !hit_record.front_face;
+            }
+            hit_records.push(hit_record);
+        }
+
+        hit_records
+    }
+}
+
+/// Whether the ray is inside `shape` at the start of `interval`, given its
+/// `crossings` within it. Without any, the first crossing after the interval
+/// tells whether the ray is inside all along.
+fn starts_inside(shape: &Shape, crossings: &[HitRecord], ray: &Ray, interval: &Range<f32>) -> bool {
+    match crossings.first() {
+        Some(hit_record) => !hit_record.front_face,
+        None => shape
+            .hits(ray, interval.end..f32::INFINITY)
+            .is_some_and(|hit_record| !hit_record.front_face),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two overlapping boxes along the x axis, the left one from -2 to 2 and
+    /// the right one from 0 to 4.
+    fn csg(operation: Operation) -> Csg {
+        let box_from = |start: f32, end: f32| {
+            let half_height = Vec3::new(0.0, 1.0, 1.0);
+            let (start, end) = (Vec3::X * start - half_height, Vec3::X * end + half_height);
+            Shape::cuboid(start, end, Material::default())
+        };
+        let (left, right) = (box_from(-2.0, 2.0), box_from(0.0, 4.0));
+        Csg::new(operation, left, right)
+    }
+
+    /// The distance and `front_face` of every crossing of a ray from `x`
+    /// along `direction` within `interval`.
+    fn crossings(
+        operation: Operation,
+        x: f32,
+        direction: f32,
+        interval: Range<f32>,
+    ) -> Vec<(f32, bool)> {
+        let ray = Ray::new(Vec3::X * x, Vec3::X * direction);
+        let csg = csg(operation);
+        csg.hits_all(&ray, interval)
+            .iter()
+            .map(|hit_record| (hit_record.t, hit_record.front_face))
+            .collect()
+    }
+
+    #[test]
+    fn crosses_the_combined_solid() {
+        let everywhere = 0.0..f32::INFINITY;
+        assert_eq!(
+            crossings(Operation::Union, -5.0, 1.0, everywhere.clone()),
+            [(3.0, true), (9.0, false)]
+        );
+        assert_eq!(
+            crossings(Operation::Intersection, -5.0, 1.0, everywhere.clone()),
+            [(5.0, true), (7.0, false)]
+        );
+        assert_eq!(
+            crossings(Operation::Difference, -5.0, 1.0, everywhere),
+            [(3.0, true), (5.0, false)]
+        );
+    }
+
+    #[test]
+    fn starts_inside_either_shape() {
+        let everywhere = 0.0..f32::INFINITY;
+        assert_eq!(crossings(Operation::Union, 1.0, 1.0, everywhere.clone()), [(3.0, false)]);
+        assert_eq!(
+            crossings(Operation::Intersection, 1.0, 1.0, everywhere.clone()),
+            [(1.0, false)]
+        );
+        assert_eq!(crossings(Operation::Difference, -1.0, 1.0, everywhere.clone()), [(1.0, false)]);
+        // Inside the part cut away, where the surface faces the ray.
+        assert_eq!(crossings(Operation::Difference, 1.0, 1.0, everywhere.clone()), []);
+        assert_eq!(
+            crossings(Operation::Difference, 1.0, -1.0, everywhere),
+            [(1.0, true), (3.0, false)]
+        );
+    }
+
+    #[test]
+    fn only_reports_crossings_within_the_interval() {
+        assert_eq!(crossings(Operation::Difference, -5.0, 1.0, 4.0..f32::INFINITY), [(5.0, false)]);
+        assert_eq!(crossings(Operation::Intersection, -5.0, 1.0, 0.0..6.0), [(5.0, true)]);
+        assert_eq!(crossings(Operation::Union, -5.0, 2.0, 2.0..f32::INFINITY), [(4.5, false)]);
+    }
+}
@@ -0,0 +1,132 @@
+use glam::Quat;
+use rand::Rng;
+
+pub use crate::shape::hittable::*;
+
+/// The faces of a box as pairs of outward normal and up direction in local
+/// space. Texture coordinates follow the same layout as `TriangleMesh::cuboid`.
+const FACES: [(Vec3, Vec3); 6] = [
+    (Vec3::X, Vec3::Y),
+    (Vec3::NEG_X, Vec3::Y),
+    (Vec3::Y, Vec3::NEG_Z),
+    (Vec3::NEG_Y, Vec3::Z),
+    (Vec3::Z, Vec3::Y),
+    (Vec3::NEG_Z, Vec3::Y),
+];
+
+/// A box intersected analytically with the slab method. Like `Mesh`, it can
+/// be rotated and translated after it is created.
+#[derive(Clone)]
+pub struct Cuboid {
+    pub center: Vec3,
+    pub half_size: Vec3,
+    pub rotation: Quat,
+    material: Material,
+}
+
+impl Cuboid {
+    /// An axis-aligned box with the given edge lengths centered at the origin.
+    pub fn new(size: Vec3, material: Material) -> Self {
+        Self {
+            center: Vec3::ZERO,
+            half_size: size.abs() / 2.0,
+            rotation: Quat::IDENTITY,
+            material,
+        }
+    }
+
+    pub fn from_corners(a: Vec3, b: Vec3, material: Material) -> Self {
+        let mut cuboid = Self::new(b - a, material);
+        cuboid.center = (a + b) / 2.0;
+        cuboid
+    }
+
+    pub fn rotate(&mut self, rotation: Quat) {
+        self.rotation = rotation * self.rotation;
+        self.center = rotation * self.center;
+    }
+
+    pub fn rotate_y(&mut self, angle: f32) {
+        self.rotate(Quat::from_rotation_y(angle.to_radians()));
+    }
+
+    pub fn translate(&mut self, translation: Vec3) {
+        self.center += translation;
+    }
+
+    pub fn area(&self) -> f32 {
+        let size = self.half_size * 2.0;
+        2.0 * (size.x * size.y + size.y * size.z + size.z * size.x)
+    }
+
+    fn face_area(&self, normal: Vec3) -> f32 {
+        // The extent along the normal is replaced by one, leaving the product
+        // of the two edges of the face.
+        let axis = normal.abs();
+        (self.half_size * 2.0 * (Vec3::ONE - axis) + axis).element_product()
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hits(&self, ray: &Ray, interval: Range<f32>) -> Option<HitRecord<'_>> {
+        let inverse = self.rotation.inverse();
+        let origin = inverse * (ray.origin - self.center);
+        let direction = inverse * ray.direction;
+
+        let inverse_direction = direction.recip();
+        let t1 = (-self.half_size - origin) * inverse_direction;
+        let t2 = (self.half_size - origin) * inverse_direction;
+        let (entry, exit) = (t1.min(t2), t1.max(t2));
+        let (t_near, t_far) = (entry.max_element(), exit.min_element());
+        if t_near > t_far {
+            return None;
+        }
+
+        // Rays enter through the face of the slab they reach last, against
+        // the direction of travel, and leave through the one they reach first.
+        let (t, axis, sign) = if interval.contains(&t_near) {
+            (t_near, (0..3).find(|axis| entry[*axis] == t_near)?, -1.0)
+        } else if interval.contains(&t_far) {
+            (t_far, (0..3).find(|axis| exit[*axis] == t_far)?, 1.0)
+        } else {
+            return None;
+        };
+        let mut normal = Vec3::ZERO;
+        normal[axis] = sign * direction[axis].signum();
+        let relative = (origin + direction * t) / self.half_size;
+
+        let outward_normal = self.rotation * normal;
+        let mut hit_record = HitRecord::new(t, ray.at(t), ray, outward_normal, &self.material);
+        let (_, up) = FACES.into_iter().find(|(face, _)| *face == normal)?;
+        let right = up.cross(normal);
+        hit_record.set_uv((
+            0.5 + 0.5 * relative.dot(right),
+            0.5 - 0.5 * relative.dot(up),
+        ));
+
+        Some(hit_record)
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        area_pdf_value(self, self.area(), origin, direction)
+    }
+
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        let mut rng = rand::thread_rng();
+        let mut choice = rng.random::<f32>() * self.area();
+        let (normal, up) = FACES
+            .into_iter()
+            .find(|(normal, _)| {
+                choice -= self.face_area(*normal);
+                choice <= 0.0
+            })
+            .unwrap_or(FACES[5]);
+        let right = up.cross(normal);
+        let local = (normal
+            + right * (rng.random::<f32>() * 2.0 - 1.0)
+            + up * (rng.random::<f32>() * 2.0 - 1.0))
+            * self.half_size;
+
+        (self.center + self.rotation * local - *origin).normalize()
+    }
+}
@@ -0,0 +1,107 @@
+use rand::Rng;
+use std::f32::consts::{PI, TAU};
+
+use crate::pdf::orthonormal_basis::OrthonormalBasis;
+pub use crate::shape::hittable::*;
+
+/// A cylinder from `base` to `base + height * axis`, optionally closed by
+/// disks at both ends.
+#[derive(Clone)]
+pub struct Cylinder {
+    pub base: Vec3,
+    pub height: f32,
+    pub radius: f32,
+    pub capped: bool,
+    basis: OrthonormalBasis,
+    material: Material,
+}
+
+impl Cylinder {
+    pub fn new(base: Vec3, top: Vec3, radius: f32, capped: bool, material: Material) -> Self {
+        Self {
+            base,
+            height: (top - base).length(),
+            radius,
+            capped,
+            basis: OrthonormalBasis::new(&(top - base)),
+            material,
+        }
+    }
+
+    pub fn side_area(&self) -> f32 {
+        TAU * self.radius * self.height
+    }
+
+    pub fn area(&self) -> f32 {
+        let cap_area = if self.capped { 2.0 * PI * self.radius * self.radius } else { 0.0 };
+        self.side_area() + cap_area
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hits(&self, ray: &Ray, interval: Range<f32>) -> Option<HitRecord<'_>> {
+        let origin = self.basis.to_local(&(ray.origin - self.base));
+        let direction = self.basis.to_local(&ray.direction);
+        let mut closest: Option<(f32, Vec3, Vec3)> = None;
+        let mut consider = |t: f32, local: Vec3, normal: Vec3| {
+            if interval.contains(&t) && closest.is_none_or(|(closest_t, _, _)| t < closest_t) {
+                closest = Some((t, local, normal));
+            }
+        };
+
+        let a = direction.x * direction.x + direction.y * direction.y;
+        let half_b = origin.x * direction.x + origin.y * direction.y;
+        let c = origin.x * origin.x + origin.y * origin.y - self.radius * self.radius;
+        let discriminant = half_b * half_b - a * c;
+        if a > 1e-12 && discriminant >= 0.0 {
+            let root = discriminant.sqrt();
+            for t in [(-half_b - root) / a, (-half_b + root) / a] {
+                let local = origin + direction * t;
+                if (0.0..=self.height).contains(&local.z) {
+                    consider(t, local, Vec3::new(local.x, local.y, 0.0) / self.radius);
+                }
+            }
+        }
+
+        if self.capped && direction.z.abs() > 1e-8 {
+            for (z, normal) in [(0.0, Vec3::NEG_Z), (self.height, Vec3::Z)] {
+                let t = (z - origin.z) / direction.z;
+                let local = origin + direction * t;
+                if local.x * local.x + local.y * local.y <= self.radius * self.radius {
+                    consider(t, local, normal);
+                }
+            }
+        }
+
+        let (t, local, normal) = closest?;
+        let outward_normal = self.basis.transform(&normal);
+        let mut hit_record = HitRecord::new(t, ray.at(t), ray, outward_normal, &self.material);
+        let uv = if normal.z == 0.0 {
+            (0.5 + local.y.atan2(local.x) / TAU, 1.0 - local.z / self.height)
+        } else {
+            (0.5 + local.x / (2.0 * self.radius), 0.5 - local.y / (2.0 * self.radius))
+        };
+        hit_record.set_uv(uv);
+
+        Some(hit_record)
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        area_pdf_value(self, self.area(), origin, direction)
+    }
+
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        let mut rng = rand::thread_rng();
+        let angle = TAU * rng.random::<f32>();
+        let local = if rng.random::<f32>() * self.area() < self.side_area() {
+            let z = self.height * rng.random::<f32>();
+            Vec3::new(self.radius * angle.cos(), self.radius * angle.sin(), z)
+        } else {
+            let distance = self.radius * rng.random::<f32>().sqrt();
+            let z = if rng.random::<bool>() { self.height } else { 0.0 };
+            Vec3::new(distance * angle.cos(), distance * angle.sin(), z)
+        };
+
+        (self.base + self.basis.transform(&local) - *origin).normalize()
+    }
+}
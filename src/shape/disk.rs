@@ -0,0 +1,93 @@
+use rand::Rng;
+use std::f32::consts::TAU;
+
+use crate::pdf::orthonormal_basis::OrthonormalBasis;
+pub use crate::shape::hittable::*;
+
+/// A flat disk facing `normal`. A non-zero `inner_radius` cuts a hole in the
+/// middle, making it an annulus.
+#[derive(Clone)]
+pub struct Disk {
+    pub center: Vec3,
+    pub inner_radius: f32,
+    pub radius: f32,
+    basis: OrthonormalBasis,
+    material: Material,
+}
+
+impl Disk {
+    pub fn new(center: Vec3, normal: Vec3, radius: f32, material: Material) -> Self {
+        Self::annulus(center, normal, 0.0, radius, material)
+    }
+
+    pub fn annulus(
+        center: Vec3,
+        normal: Vec3,
+        inner_radius: f32,
+        radius: f32,
+        material: Material,
+    ) -> Self {
+        Self {
+            center,
+            inner_radius,
+            radius,
+            basis: OrthonormalBasis::new(&normal),
+            material,
+        }
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.basis.w
+    }
+
+    pub fn area(&self) -> f32 {
+        std::f32::consts::PI * (self.radius * self.radius - self.inner_radius * self.inner_radius)
+    }
+}
+
+impl Hittable for Disk {
+    fn hits(&self, ray: &Ray, interval: Range<f32>) -> Option<HitRecord<'_>> {
+        let origin = self.basis.to_local(&(ray.origin - self.center));
+        let direction = self.basis.to_local(&ray.direction);
+        if direction.z.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = -origin.z / direction.z;
+        if !interval.contains(&t) {
+            return None;
+        }
+
+        let local = origin + direction * t;
+        let distance_squared = local.x * local.x + local.y * local.y;
+        if distance_squared > self.radius * self.radius
+            || distance_squared < self.inner_radius * self.inner_radius
+        {
+            return None;
+        }
+
+        let mut hit_record = HitRecord::new(t, ray.at(t), ray, self.basis.w, &self.material);
+        hit_record.set_uv((
+            0.5 + local.x / (2.0 * self.radius),
+            0.5 - local.y / (2.0 * self.radius),
+        ));
+
+        Some(hit_record)
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        area_pdf_value(self, self.area(), origin, direction)
+    }
+
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        let mut rng = rand::thread_rng();
+        let inner_squared = self.inner_radius * self.inner_radius;
+        let distance = (inner_squared
+            + rng.random::<f32>() * (self.radius * self.radius - inner_squared))
+            .sqrt();
+        let angle = TAU * rng.random::<f32>();
+        let local = Vec3::new(distance * angle.cos(), distance * angle.sin(), 0.0);
+
+        (self.center + self.basis.transform(&local) - *origin).normalize()
+    }
+}
@@ -0,0 +1,30 @@
+use crate::environment_map::EnvironmentMap;
+pub use crate::shape::hittable::*;
+
+/// The environment map as a light, made by `EnvironmentMap::light`. Like the
+/// sun it lies at infinity and is seen through the background, so it is
+/// never hit.
+#[derive(Clone)]
+pub struct EnvironmentLight {
+    map: EnvironmentMap,
+}
+
+impl EnvironmentLight {
+    pub fn new(map: EnvironmentMap) -> Self {
+        Self { map }
+    }
+}
+
+impl Hittable for EnvironmentLight {
+    fn hits(&self, _ray: &Ray, _interval: Range<f32>) -> Option<HitRecord<'_>> {
+        None
+    }
+
+    fn pdf_value(&self, _origin: &Vec3, direction: &Vec3) -> f32 {
+        self.map.pdf(*direction)
+    }
+
+    fn random(&self, _origin: &Vec3) -> Vec3 {
+        self.map.sample()
+    }
+}
@@ -0,0 +1,227 @@
+use rand::Rng;
+
+pub use crate::shape::hittable::*;
+use crate::shape::Shape;
+use crate::texture::density_grid::DensityGrid;
+use crate::texture::{ColorValue, Texture};
+
+/// A participating medium whose density varies inside its boundary, given by
+/// a texture sampled at each point, such as a `DensityGrid` or noise. The
+/// coefficients are per unit of density: absorbed light is lost or replaced
+/// by `emission`, scattered light changes direction uniformly.
+///
+/// Collisions are found with delta tracking against `max_density`, which has
+/// to be at least the largest value of the density texture.
+#[derive(Clone)]
+pub struct HeterogeneousMedium {
+    boundary: Box<Shape>,
+    density: Texture,
+    max_density: f32,
+    pub absorption: f32,
+    pub scattering: f32,
+    phase_function: Material,
+    albedo: Option<Texture>,
+    emission: Material,
+}
+
+impl HeterogeneousMedium {
+    pub fn new(boundary: Shape, density: Texture, max_density: f32) -> Self {
+        Self {
+            boundary: Box::new(boundary),
+            density,
+            max_density,
+            absorption: 0.0,
+            scattering: 1.0,
+            phase_function: Material::isotropic_from_vec3(Vec3::ONE),
+            albedo: None,
+            emission: Material::diffuse_light(Vec3::ZERO),
+        }
+    }
+
+    pub fn from_grid(boundary: Shape, grid: DensityGrid) -> Self {
+        let max_density = grid.max_value();
+        Self::new(boundary, Texture::density_grid(grid), max_density)
+    }
+
+    pub fn with_coefficients(mut self, absorption: f32, scattering: f32) -> Self {
+        self.absorption = absorption;
+        self.scattering = scattering;
+        self
+    }
+
+    /// Scatters with another phase function than the default isotropic one.
+    pub fn with_phase_function(mut self, phase_function: Material) -> Self {
+        self.phase_function = phase_function;
+        self.apply_albedo();
+        self
+    }
+
+    /// The color of scattered light, which replaces that of the phase
+    /// function whether it is set before or after it.
+    pub fn with_albedo(mut self, albedo: Texture) -> Self {
+        self.albedo = Some(albedo);
+        self.apply_albedo();
+        self
+    }
+
+    /// The radiance emitted where light is absorbed, for fire and glowing gas.
+    pub fn with_emission(mut self, emission: Texture) -> Self {
+        self.emission = Material::diffuse_light_from_texture(emission);
+        self
+    }
+
+    pub fn density(&self, point: Vec3) -> f32 {
+        self.density.get_color_value(0.0, 0.0, point).element_sum() / 3.0
+    }
+
+    fn apply_albedo(&mut self) {
+        let Some(albedo) = self.albedo.clone() else {
+            return;
+        };
+        match &mut self.phase_function {
+            Material::Isotropic(isotropic) => isotropic.albedo = albedo,
+            Material::HenyeyGreenstein(phase) => phase.albedo = albedo,
+            _ => {}
+        }
+    }
+
+    /// A distance along the ray, in units of `t`, to the next tentative
+    /// collision with a medium of density `max_density` everywhere.
+    fn free_path(&self, ray: &Ray, random: f32) -> f32 {
+        let majorant = self.max_density * (self.absorption + self.scattering);
+        -(1.0 - random).ln() / (majorant * ray.direction.length())
+    }
+}
+
+impl Hittable for HeterogeneousMedium {
+    fn hits(&self, ray: &Ray, interval: Range<f32>) -> Option<HitRecord<'_>> {
+        let extinction = self.absorption + self.scattering;
+        if self.max_density <= 0.0 || extinction <= 0.0 {
+            return None;
+        }
+
+        let mut rng = rand::thread_rng();
+        for segment in inside_segments(self.boundary.as_ref(), ray, interval) {
+            let mut t = segment.start;
+            loop {
+                t += self.free_path(ray, rng.random::<f32>());
+                if t >= segment.end {
+                    break;
+                }
+
+                // Tentative collisions are real in proportion to the density
+                // there; the others leave the ray unchanged.
+                let hit_point = ray.at(t);
+                if rng.random::<f32>() * self.max_density >= self.density(hit_point) {
+                    continue;
+                }
+
+                let material = if rng.random::<f32>() * extinction < self.absorption {
+                    &self.emission
+                } else {
+                    &self.phase_function
+                };
+                let normal = Vec3::X;
+
+                return Some(HitRecord {
+                    t,
+                    hit_point,
+                    normal,
+                    geometric_normal: normal,
+                    ray: *ray,
+                    front_face: true,
+                    material,
+                    u: 0.0,
+                    v: 0.0,
+                    light_channels: DEFAULT_LIGHT_CHANNELS,
+                });
+            }
+        }
+
+        None
+    }
+
+    /// Estimated with ratio tracking, which weakens the light at every
+    /// tentative collision instead of stopping it at a real one, so shadows
+    /// through the medium are smooth rather than noisy.
+    fn transmittance(&self, ray: &Ray, interval: Range<f32>) -> f32 {
+        let mut rng = rand::thread_rng();
+        let mut transmittance = 1.0;
+        for segment in inside_segments(self.boundary.as_ref(), ray, interval) {
+            let mut t = segment.start;
+            loop {
+                t += self.free_path(ray, rng.random::<f32>());
+                if t >= segment.end {
+                    break;
+                }
+                transmittance *= 1.0 - self.density(ray.at(t)) / self.max_density;
+            }
+        }
+
+        transmittance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLES: usize = 20000;
+
+    /// A medium with a density of one half, at most one, inside the box from
+    /// -1 to 1, with an extinction of 0.75 per unit of length.
+    fn medium() -> HeterogeneousMedium {
+        let boundary = Shape::cuboid(Vec3::NEG_ONE, Vec3::ONE, Material::default());
+        HeterogeneousMedium::new(boundary, Texture::solid_color(Vec3::splat(0.5)), 1.0)
+            .with_coefficients(0.5, 1.0)
+    }
+
+    #[test]
+    fn estimates_transmittance_with_ratio_tracking() {
+        let medium = medium();
+        for (origin, direction, length) in [
+            (Vec3::X * -5.0, Vec3::X, 2.0),
+            (Vec3::X * -5.0, Vec3::X * 2.0, 2.0),
+            (Vec3::ZERO, Vec3::Y, 1.0),
+        ] {
+            let ray = Ray::new(origin, direction);
+            let mean = (0..SAMPLES)
+                .map(|_| medium.transmittance(&ray, 0.0..f32::INFINITY))
+                .sum::<f32>()
+                / SAMPLES as f32;
+            let expected = (-0.75f32 * length).exp();
+            assert!((mean - expected).abs() < 0.015, "{} != {}", mean, expected);
+        }
+    }
+
+    #[test]
+    fn collides_as_often_as_light_is_blocked() {
+        let medium = medium();
+        let ray = Ray::new(Vec3::X * -5.0, Vec3::X);
+        let collisions = (0..SAMPLES)
+            .filter_map(|_| medium.hits(&ray, 0.0..f32::INFINITY))
+            .inspect(|hit_record| assert!((4.0..6.0).contains(&hit_record.t)))
+            .count();
+        let expected = 1.0 - (-1.5f32).exp();
+        let fraction = collisions as f32 / SAMPLES as f32;
+        assert!((fraction - expected).abs() < 0.015, "{} != {}", fraction, expected);
+    }
+
+    #[test]
+    fn keeps_the_albedo_whatever_the_order() {
+        let albedo = Vec3::new(0.2, 0.4, 0.6);
+        let phase = || Material::henyey_greenstein(Texture::solid_color(Vec3::ONE), 0.5);
+        for medium in [
+            medium().with_albedo(Texture::solid_color(albedo)).with_phase_function(phase()),
+            medium().with_phase_function(phase()).with_albedo(Texture::solid_color(albedo)),
+        ] {
+            match &medium.phase_function {
+                Material::HenyeyGreenstein(phase) => {
+                    assert_eq!(phase.albedo.get_color_value(0.0, 0.0, Vec3::ZERO), albedo);
+                    assert_eq!(phase.asymmetry, 0.5);
+                }
+                _ => panic!("expected the Henyey-Greenstein phase function"),
+            }
+        }
+    }
+}
@@ -1,5 +1,6 @@
 pub use crate::camera::ray::*;
 pub use crate::material::*;
+pub use crate::light::{ALL_LIGHT_CHANNELS, DEFAULT_LIGHT_CHANNELS};
 pub use std::ops::Range;
 use rand::prelude::IteratorRandom;
 
@@ -7,11 +8,15 @@ pub struct HitRecord<'a> {
     pub t: f32,
     pub hit_point: Vec3,
     pub normal: Vec3,
+    pub geometric_normal: Vec3,
     pub ray: Ray,
     pub front_face: bool,
     pub u: f32,
     pub v: f32,
     pub material: &'a Material,
+    /// The light channels of the object that was hit, set by
+    /// `ObjectSettings`.
+    pub light_channels: u32,
 }
 
 impl<'a> HitRecord<'a> {
@@ -33,14 +38,24 @@ impl<'a> HitRecord<'a> {
             t,
             hit_point,
             normal,
+            geometric_normal: normal,
             ray: *ray,
             front_face,
             material,
             u: 0.0,
             v: 0.0,
+            light_channels: DEFAULT_LIGHT_CHANNELS,
         }
     }
 
+    pub fn set_shading_normal(&mut self, shading_normal: Vec3) {
+        self.normal = if shading_normal.dot(self.geometric_normal) < 0.0 {
+            -shading_normal
+        } else {
+            shading_normal
+        };
+    }
+
     pub fn set_uv(&mut self, (u, v): (f32, f32)) {
         self.u = u;
         self.v = v;
@@ -48,7 +63,32 @@ impl<'a> HitRecord<'a> {
 }
 
 pub trait Hittable {
-    fn hits(&self, ray: &Ray, interval: Range<f32>) -> Option<HitRecord>;
+    fn hits(&self, ray: &Ray, interval: Range<f32>) -> Option<HitRecord<'_>>;
+
+    /// Every place the ray crosses the surface within `interval`, sorted by
+    /// distance. For closed shapes `front_face` tells whether the ray enters
+    /// or leaves the inside at each crossing.
+    fn hits_all(&self, ray: &Ray, interval: Range<f32>) -> Vec<HitRecord<'_>> {
+        let mut hit_records = Vec::new();
+        let mut start = interval.start;
+        while let Some(hit_record) = self.hits(ray, start..interval.end) {
+            start = next_start(ray, hit_record.t);
+            hit_records.push(hit_record);
+        }
+
+        hit_records
+    }
+
+    /// The fraction of light passing along the ray within `interval`. Surfaces
+    /// block all of it, while media may let part of it through.
+    fn transmittance(&self, ray: &Ray, interval: Range<f32>) -> f32 {
+        if self.hits(ray, interval).is_some() {
+            0.0
+        } else {
+            1.0
+        }
+    }
+
     fn pdf_value(&self, _origin: &Vec3, _direction: &Vec3) -> f32 {
         0.0
     }
@@ -57,8 +97,56 @@ pub trait Hittable {
     }
 }
 
+/// The solid angle density of sampling points uniformly over a surface of the
+/// given `area`, summed over every place the ray from `origin` crosses it.
+pub fn area_pdf_value(shape: &impl Hittable, area: f32, origin: &Vec3, direction: &Vec3) -> f32 {
+    let ray = Ray::new(*origin, *direction);
+    shape
+        .hits_all(&ray, 0.001..f32::INFINITY)
+        .iter()
+        .map(|hit_record| {
+            let distance_squared = hit_record.t * hit_record.t * direction.length_squared();
+            let cosine = direction.dot(hit_record.geometric_normal).abs() / direction.length();
+            distance_squared / (cosine * area)
+        })
+        .sum()
+}
+
+/// The parts of the ray within `interval` that are inside a closed shape,
+/// in order. Whether the ray starts inside is decided by crossings before
+/// the interval, and a ray that never leaves stays inside to infinity.
+pub fn inside_segments(shape: &impl Hittable, ray: &Ray, interval: Range<f32>) -> Vec<Range<f32>> {
+    let mut segments = Vec::new();
+    let mut entry = None;
+    for hit_record in shape.hits_all(ray, f32::NEG_INFINITY..f32::INFINITY) {
+        if hit_record.front_face {
+            entry.get_or_insert(hit_record.t);
+        } else {
+            segments.push(entry.take().unwrap_or(f32::NEG_INFINITY)..hit_record.t);
+        }
+    }
+    if let Some(entry) = entry {
+        segments.push(entry..f32::INFINITY);
+    }
+
+    segments
+        .into_iter()
+        .map(|segment| segment.start.max(interval.start)..segment.end.min(interval.end))
+        .filter(|segment| segment.start < segment.end)
+        .collect()
+}
+
+/// The start of the interval searched for the next crossing after one at `t`,
+/// far enough along to not find the same one again. Rounding errors in a
+/// crossing grow with the coordinates involved, so the step is relative to
+/// them and features of any size are found at any scale.
+pub(crate) fn next_start(ray: &Ray, t: f32) -> f32 {
+    let scale = t.abs() + ray.origin.abs().max_element() / ray.direction.length();
+    (t + scale * f32::EPSILON * 16.0).max(t.next_up())
+}
+
 impl<T: Hittable> Hittable for Vec<T> {
-    fn hits(&self, ray: &Ray, interval: Range<f32>) -> Option<HitRecord> {
+    fn hits(&self, ray: &Ray, interval: Range<f32>) -> Option<HitRecord<'_>> {
         let (_, hit_record) = self.iter()
             .fold((interval.end, None), |(closest_t, closest_hit), shape| {
                 if let Some(hit) = shape.hits(ray, interval.start..closest_t) {
@@ -71,6 +159,18 @@ impl<T: Hittable> Hittable for Vec<T> {
         hit_record
     }
 
+    fn transmittance(&self, ray: &Ray, interval: Range<f32>) -> f32 {
+        let mut transmittance = 1.0;
+        for shape in self {
+            transmittance *= shape.transmittance(ray, interval.clone());
+            if transmittance == 0.0 {
+                break;
+            }
+        }
+
+        transmittance
+    }
+
     fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f32 {
         let weight = 1.0 / self.len() as f32;
         self.iter()
@@ -1,69 +1,208 @@
-use crate::shape::triangle::*;
+use std::collections::HashMap;
+use rand::Rng;
+
+use crate::error::Result;
+use crate::loader;
+use crate::shape::mesh_cleanup::{CleanupReport, MeshStats};
+use crate::shape::triangle_mesh::*;
+use crate::texture::Texture;
 
 #[derive(Clone)]
 pub struct Mesh {
-    pub triangles: Vec<Triangle>,
+    pub geometry: TriangleMesh,
+    pub materials: Vec<Material>,
+    pub material_names: Vec<String>,
+    pub normal_maps: Vec<Option<Texture>>,
+    /// What was fixed when the mesh was imported.
+    pub cleanup_report: CleanupReport,
     bvh_nodes: Vec<BVHNode>,
     triangle_indices: Vec<usize>,
     centroids: Vec<Vec3>,
+    /// The running total of the triangle areas, for picking triangles in
+    /// proportion to their area when the mesh is sampled as a light.
+    area_sums: Vec<f32>,
     nodes_used: usize,
     pub center: Vec3,
 }
 
 impl Mesh {
     pub fn new(path_to_model: &str, material: Material) -> Self {
-        let model = match wavefront::Obj::from_file(path_to_model) {
-            Ok(object) => object,
-            Err(_) => {
-                eprintln!("could not load model {}", path_to_model);
-                panic!("could not load model");
-            }
-        };
+        Self::load(path_to_model, material).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn with_crease_angle(path_to_model: &str, material: Material, crease_angle: f32) -> Self {
+        Self::load_with_crease_angle(path_to_model, material, crease_angle)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Loads an OBJ model together with the materials of its `mtllib` files.
+    /// Faces whose material is not found in any library use the default material.
+    pub fn from_obj(path_to_model: &str) -> Self {
+        Self::try_from_obj(path_to_model).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn load(path_to_model: &str, material: Material) -> Result<Self> {
+        Self::load_with_crease_angle(path_to_model, material, DEFAULT_CREASE_ANGLE)
+    }
 
-        let triangles: Vec<Triangle> = model.triangles()
-            .map(|[p1, p2, p3]| {
-                let p1 = Vec3::from_array(p1.position());
-                let p2 = Vec3::from_array(p2.position());
-                let p3 = Vec3::from_array(p3.position());
-                Triangle::new(p1, p2, p3, material.clone())
+    pub fn load_with_crease_angle(
+        path_to_model: &str,
+        material: Material,
+        crease_angle: f32,
+    ) -> Result<Self> {
+        let model = Self::ensure_normals(loader::load(path_to_model)?, crease_angle);
+        Ok(Self::from_model(model, material))
+    }
+
+    /// Loads a model without welding vertices or fixing its triangles, for
+    /// geometry that must stay exactly as stored.
+    pub fn load_without_cleanup(path_to_model: &str, material: Material) -> Result<Self> {
+        let model = loader::load_without_cleanup(path_to_model)?;
+        Ok(Self::from_model(Self::ensure_normals(model, DEFAULT_CREASE_ANGLE), material))
+    }
+
+    fn from_model(model: loader::Model, material: Material) -> Self {
+        let materials = vec![material; model.material_names.len()];
+        let mut mesh = Self::from_geometry(model.geometry, materials);
+        mesh.material_names = model.material_names;
+        mesh.cleanup_report = model.cleanup_report;
+
+        mesh
+    }
+
+    pub fn try_from_obj(path_to_model: &str) -> Result<Self> {
+        let model = Self::ensure_normals(loader::load(path_to_model)?, DEFAULT_CREASE_ANGLE);
+        let mut library = HashMap::new();
+        for path in &model.material_libraries {
+            library.extend(loader::mtl::load(path)?);
+        }
+        let materials = model
+            .material_names
+            .iter()
+            .map(|name| match library.get(name) {
+                Some(material) => material.to_material(),
+                None => Ok(Material::default()),
             })
-            .collect();
+            .collect::<Result<_>>()?;
+        let mut mesh = Self::from_geometry(model.geometry, materials);
+        mesh.material_names = model.material_names;
+        mesh.cleanup_report = model.cleanup_report;
 
+        Ok(mesh)
+    }
+
+    fn ensure_normals(mut model: loader::Model, crease_angle: f32) -> loader::Model {
+        if model.geometry.normals.is_none() {
+            model.geometry.compute_normals(crease_angle);
+        }
+
+        model
+    }
+
+    pub fn from_geometry(geometry: TriangleMesh, materials: Vec<Material>) -> Self {
         let mut model = Mesh {
+            material_names: vec![String::new(); materials.len()],
+            normal_maps: vec![None; materials.len()],
+            cleanup_report: CleanupReport::default(),
             bvh_nodes: Vec::new(),
             triangle_indices: Vec::new(),
             centroids: Vec::new(),
-            triangles,
+            area_sums: Vec::new(),
+            geometry,
+            materials,
             center: Vec3::ZERO,
             nodes_used: 0,
         };
         model.build_bvh();
-        
+
         model
     }
 
+    pub fn add_material(&mut self, material: Material) -> u32 {
+        self.materials.push(material);
+        self.material_names.push(String::new());
+        self.normal_maps.push(None);
+        (self.materials.len() - 1) as u32
+    }
+
+    pub fn set_material(&mut self, index: u32, material: Material) {
+        self.materials[index as usize] = material;
+    }
+
+    pub fn set_normal_map(&mut self, index: u32, normal_map: Texture) {
+        self.normal_maps[index as usize] = Some(normal_map);
+    }
+
+    /// Replaces the material with the given name, returning whether the mesh
+    /// has a material by that name.
+    pub fn override_material(&mut self, name: &str, material: Material) -> bool {
+        match self.material_names.iter().position(|n| n == name) {
+            Some(index) => {
+                self.materials[index] = material;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn smooth_normals(&mut self, crease_angle: f32) {
+        self.geometry.compute_normals(crease_angle);
+    }
+
+    pub fn flat_shading(&mut self) {
+        self.geometry.normals = None;
+    }
+
+    /// Saves the geometry as it is traced, including transforms, to an OBJ
+    /// or PLY file depending on the extension of `path`.
+    pub fn save(&self, path: &str) -> Result<()> {
+        loader::save(path, &self.geometry, &self.material_names)
+    }
+
+    pub fn stats(&self) -> MeshStats {
+        self.geometry.stats()
+    }
+
+    /// Cleans up the geometry again with an absolute weld tolerance.
+    pub fn cleanup(&mut self, weld_tolerance: f32) -> CleanupReport {
+        let report = self.geometry.cleanup(weld_tolerance);
+        self.build_bvh();
+
+        report
+    }
+
     fn build_bvh(&mut self) {
-        self.bvh_nodes = vec![BVHNode::default(); self.triangles.len() * 2 - 1];
+        let node_count = (self.geometry.triangles.len() * 2).max(2) - 1;
+        self.bvh_nodes = vec![BVHNode::default(); node_count];
         self.nodes_used = 1;
-        self.bvh_nodes[0].num_triangles = self.triangles.len() as u32;
-        self.triangle_indices = (0..self.triangles.len()).collect();
-        self.centroids = self.triangles.iter()
-            .map(|t| (t.p1 + t.p2 + t.p3) / 3.0)
+        self.bvh_nodes[0].num_triangles = self.geometry.triangles.len() as u32;
+        self.triangle_indices = (0..self.geometry.triangles.len()).collect();
+        self.centroids = self.geometry.triangles.iter()
+            .map(|triangle| triangle.centroid(&self.geometry.positions))
             .collect();
         self.update_node_bounds(0);
         self.subdivide(0);
+
+        let mut area = 0.0;
+        self.area_sums = self.geometry.triangles.iter()
+            .map(|triangle| {
+                area += triangle.area(&self.geometry.positions);
+                area
+            })
+            .collect();
     }
 
     fn update_node_bounds(&mut self, node_index: usize) {
         let node = &mut self.bvh_nodes[node_index];
         (node.index..node.index + node.num_triangles).for_each(|i| {
             let triangle_index = self.triangle_indices[i as usize];
-            let triangle = &self.triangles[triangle_index];
-            node.aabb.min = node.aabb.min.min(triangle.p1).min(triangle.p2).min(triangle.p3);
-            node.aabb.max = node.aabb.max.max(triangle.p1).max(triangle.p2).max(triangle.p3);
+            let triangle = &self.geometry.triangles[triangle_index];
+            let [p1, p2, p3] = self.geometry.positions(triangle);
+            node.aabb.min = node.aabb.min.min(p1).min(p2).min(p3);
+            node.aabb.max = node.aabb.max.max(p1).max(p2).max(p3);
         });
-        node.aabb.min = node.aabb.min - Vec3::splat(0.0001);
-        node.aabb.max = node.aabb.max + Vec3::splat(0.0001);
+        node.aabb.min -= Vec3::splat(0.0001);
+        node.aabb.max += Vec3::splat(0.0001);
     }
 
     fn subdivide(&mut self, node_index: usize) {
@@ -144,18 +283,18 @@ impl Mesh {
         let mut right = AABB::default();
         let mut left_count = 0;
         let mut right_count = 0;
-        (0..node.num_triangles).into_iter()
+        (0..node.num_triangles)
             .map(|i| self.triangle_indices[(node.index + i) as usize])
             .for_each(|index| {
                 let centroid = self.centroids[index];
-                let triangle = &self.triangles[index];
+                let [p1, p2, p3] = self.geometry.positions(&self.geometry.triangles[index]);
                 if centroid[axis] < position {
-                    left.min = left.min.min(triangle.p1).min(triangle.p2).min(triangle.p3);
-                    left.max = left.max.max(triangle.p1).max(triangle.p2).max(triangle.p3);
+                    left.min = left.min.min(p1).min(p2).min(p3);
+                    left.max = left.max.max(p1).max(p2).max(p3);
                     left_count += 1;
                 } else {
-                    right.min = right.min.min(triangle.p1).min(triangle.p2).min(triangle.p3);
-                    right.max = right.max.max(triangle.p1).max(triangle.p2).max(triangle.p3);
+                    right.min = right.min.min(p1).min(p2).min(p3);
+                    right.max = right.max.max(p1).max(p2).max(p3);
                     right_count += 1;
                 }
             });
@@ -164,10 +303,13 @@ impl Mesh {
         (cost > 0.0).then_some(cost)
     }
 
-    fn transverse_bvh(&self, ray: &Ray, interval: Range<f32>) -> Option<HitRecord> {
+    fn transverse_bvh(&self, ray: &Ray, interval: Range<f32>) -> Option<HitRecord<'_>> {
         let mut stack = Vec::with_capacity(64);
         let mut closest_t = interval.end;
         let mut hit_record = None;
+        if self.geometry.triangles.is_empty() {
+            return None;
+        }
         stack.push(0);
 
         while !stack.is_empty() {
@@ -175,9 +317,13 @@ impl Mesh {
             if node.is_leaf() {
                 (closest_t, hit_record) = (0..node.num_triangles)
                     .map(|i| self.triangle_indices[(node.index + i) as usize])
-                    .map(|triangle_index| &self.triangles[triangle_index])
+                    .map(|triangle_index| &self.geometry.triangles[triangle_index])
                     .fold((closest_t, hit_record), |(closest_t, closest_hit), triangle| {
-                        if let Some(hit) = triangle.hits(ray, interval.start..closest_t) {
+                        let material = &self.materials[triangle.material as usize];
+                        let normal_map = self.normal_maps[triangle.material as usize].as_ref();
+                        let range = interval.start..closest_t;
+                        let hit = triangle.hits(&self.geometry, material, normal_map, ray, range);
+                        if let Some(hit) = hit {
                             (hit.t, Some(hit))
                         } else {
                             (closest_t, closest_hit)
@@ -192,8 +338,8 @@ impl Mesh {
             let left_node = &self.bvh_nodes[left_child_index];
             let right_node = &self.bvh_nodes[right_child_index];
 
-            let distance_left = left_node.ray_aabb_distance(ray, closest_t);
-            let distance_right = right_node.ray_aabb_distance(ray, closest_t);
+            let distance_left = left_node.ray_aabb_distance(ray, interval.start..closest_t);
+            let distance_right = right_node.ray_aabb_distance(ray, interval.start..closest_t);
 
             match (distance_left, distance_right) {
                 (Some(left_t), Some(right_t)) if left_t > right_t => {
@@ -213,35 +359,58 @@ impl Mesh {
         hit_record
     }
     
+    pub fn area(&self) -> f32 {
+        self.area_sums.last().copied().unwrap_or(0.0)
+    }
+
+    pub fn transform(&mut self, transform_matrix: &Mat4) {
+        self.geometry.transform(transform_matrix);
+        self.build_bvh();
+    }
+
     pub fn rotate_y(&mut self, angle: f32) {
         let rotation = Mat4::from_rotation_y(angle.to_radians());
-        for triangle in self.triangles.iter_mut() {
-            triangle.transform(&rotation);
-        }
-        self.build_bvh();
+        self.transform(&rotation);
     }
     
     pub fn translate(&mut self, translation: Vec3) {
         let translation = Mat4::from_translation(translation);
-        for triangle in self.triangles.iter_mut() {
-            triangle.transform(&translation);
-        }
-        self.build_bvh();
+        self.transform(&translation);
     }
     
     pub fn scale(&mut self, scale: Vec3) {
         let scale = Mat4::from_scale(scale);
-        for triangle in self.triangles.iter_mut() {
-            triangle.transform(&scale);
-        }
-        self.build_bvh();
+        self.transform(&scale);
     }
 }
 
 impl Hittable for Mesh {
-    fn hits(&self, ray: &Ray, interval: Range<f32>) -> Option<HitRecord> {
+    fn hits(&self, ray: &Ray, interval: Range<f32>) -> Option<HitRecord<'_>> {
         self.transverse_bvh(ray, interval)
     }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        if self.area() <= 0.0 {
+            return 0.0;
+        }
+
+        area_pdf_value(self, self.area(), origin, direction)
+    }
+
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        if self.area() <= 0.0 {
+            return Vec3::X;
+        }
+
+        let target = rand::thread_rng().random::<f32>() * self.area();
+        let index = self
+            .area_sums
+            .partition_point(|&sum| sum <= target)
+            .min(self.area_sums.len() - 1);
+        let point = self.geometry.triangles[index].random_point(&self.geometry.positions);
+
+        (point - *origin).normalize()
+    }
 }
 
 #[derive(Clone)]
@@ -293,14 +462,14 @@ impl BVHNode {
         self.num_triangles > 0
     }
 
-    pub fn ray_aabb_distance(&self, ray: &Ray, closest_t: f32) -> Option<f32> {
+    pub fn ray_aabb_distance(&self, ray: &Ray, interval: Range<f32>) -> Option<f32> {
         let t_min = (self.aabb.min - ray.origin) / ray.direction;
         let t_max = (self.aabb.max - ray.origin) / ray.direction;
         let t1 = t_min.min(t_max);
         let t2 = t_min.max(t_max);
         let t_near = t1.max_element();
         let t_far = t2.min_element();
-        let did_hit = t_near < t_far && t_far > 0.001 && t_near < closest_t;
+        let did_hit = t_near < t_far && t_far > interval.start && t_near < interval.end;
 
         did_hit.then_some(t_near)
     }
@@ -0,0 +1,441 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+use crate::shape::triangle_mesh::*;
+
+/// Vertices closer than this fraction of the bounding box diagonal are welded
+/// when a mesh is imported.
+pub const DEFAULT_WELD_TOLERANCE: f32 = 1e-6;
+
+/// What `TriangleMesh::cleanup` changed.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CleanupReport {
+    pub welded_vertices: usize,
+    pub unused_vertices: usize,
+    pub degenerate_triangles: usize,
+    pub duplicate_triangles: usize,
+    pub flipped_triangles: usize,
+}
+
+impl CleanupReport {
+    pub fn changed(&self) -> bool {
+        *self != Self::default()
+    }
+}
+
+impl fmt::Display for CleanupReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "welded {} vertices, removed {} unused vertices, {} degenerate and {} duplicate \
+             triangles, flipped {} triangles",
+            self.welded_vertices,
+            self.unused_vertices,
+            self.degenerate_triangles,
+            self.duplicate_triangles,
+            self.flipped_triangles,
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct MeshStats {
+    pub vertex_count: usize,
+    pub triangle_count: usize,
+    pub material_count: usize,
+    pub min: Vec3,
+    pub max: Vec3,
+    pub surface_area: f32,
+    /// Edges used by a single triangle. A closed mesh has none.
+    pub boundary_edges: usize,
+    /// Edges shared by more than two triangles.
+    pub non_manifold_edges: usize,
+    pub has_normals: bool,
+    pub has_uvs: bool,
+    pub has_tangents: bool,
+    pub has_colors: bool,
+}
+
+impl fmt::Display for MeshStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} vertices, {} triangles, {} materials, bounds {} to {}, area {}, \
+             {} boundary and {} non-manifold edges",
+            self.vertex_count,
+            self.triangle_count,
+            self.material_count,
+            self.min,
+            self.max,
+            self.surface_area,
+            self.boundary_edges,
+            self.non_manifold_edges,
+        )
+    }
+}
+
+impl TriangleMesh {
+    /// Welds vertices closer than `weld_tolerance` whose attributes match,
+    /// removes degenerate and duplicate triangles, makes the winding of
+    /// connected triangles consistent and drops vertices no triangle uses.
+    pub fn cleanup(&mut self, weld_tolerance: f32) -> CleanupReport {
+        let mut report = CleanupReport {
+            welded_vertices: self.weld_vertices(weld_tolerance),
+            ..Default::default()
+        };
+
+        let triangle_count = self.triangles.len();
+        let positions = &self.positions;
+        self.triangles.retain(|triangle| !is_degenerate(triangle.positions(positions)));
+        report.degenerate_triangles = triangle_count - self.triangles.len();
+
+        let triangle_count = self.triangles.len();
+        let mut seen = std::collections::HashSet::new();
+        self.triangles.retain(|triangle| {
+            // Rotated to start at the lowest index, which keeps the winding,
+            // so back to back faces and faces of different materials stay.
+            let mut key = triangle.vertices;
+            let lowest = (0..3).min_by_key(|&i| key[i]).unwrap();
+            key.rotate_left(lowest);
+            seen.insert((key, triangle.material))
+        });
+        report.duplicate_triangles = triangle_count - self.triangles.len();
+
+        report.flipped_triangles = self.orient_triangles();
+        report.unused_vertices = self.remove_unused_vertices() - report.welded_vertices;
+
+        report
+    }
+
+    pub fn stats(&self) -> MeshStats {
+        let first = self.positions.first().copied().unwrap_or_default();
+        let (min, max) = self.positions.iter().fold((first, first), |(min, max), position| {
+            (min.min(*position), max.max(*position))
+        });
+        let surface_area = self.triangles.iter().fold(0.0, |area, triangle| {
+            let [p1, p2, p3] = self.positions(triangle);
+            area + 0.5 * (p2 - p1).cross(p3 - p1).length()
+        });
+        let mut materials: Vec<u32> = self.triangles.iter().map(|t| t.material).collect();
+        materials.sort_unstable();
+        materials.dedup();
+        let edges = self.edge_triangles();
+
+        MeshStats {
+            vertex_count: self.vertex_count(),
+            triangle_count: self.triangle_count(),
+            material_count: materials.len(),
+            min,
+            max,
+            surface_area,
+            boundary_edges: edges.values().filter(|triangles| triangles.len() == 1).count(),
+            non_manifold_edges: edges.values().filter(|triangles| triangles.len() > 2).count(),
+            has_normals: self.normals.is_some(),
+            has_uvs: self.uvs.is_some(),
+            has_tangents: self.tangents.is_some(),
+            has_colors: self.colors.is_some(),
+        }
+    }
+
+    /// Points every triangle at the first vertex within `tolerance` that has
+    /// the same attributes, using a grid with `tolerance` sized cells.
+    /// Returns how many vertices were merged away.
+    fn weld_vertices(&mut self, tolerance: f32) -> usize {
+        // Cells much smaller than the mesh, as for an exact weld with a zero
+        // tolerance, would put cell coordinates out of the range of integers.
+        let extent = self
+            .positions
+            .iter()
+            .map(|position| position.abs().max_element())
+            .filter(|extent| extent.is_finite())
+            .fold(0.0, f32::max);
+        let cell_size = tolerance.max(extent / (1 << 20) as f32).max(f32::MIN_POSITIVE);
+        let cell = |position: Vec3| (position / cell_size).floor().as_ivec3();
+
+        let mut grid: HashMap<_, Vec<u32>> = HashMap::new();
+        let mut remap = Vec::with_capacity(self.positions.len());
+        let mut welded = 0;
+        for (index, position) in self.positions.iter().enumerate() {
+            let center = cell(*position);
+            let neighbour = (-1..=1)
+                .flat_map(|x| (-1..=1).flat_map(move |y| (-1..=1).map(move |z| (x, y, z))))
+                .filter_map(|(x, y, z)| grid.get(&(center + glam::IVec3::new(x, y, z))))
+                .flatten()
+                .copied()
+                .find(|other| {
+                    self.positions[*other as usize].distance(*position) <= tolerance
+                        && self.attributes_match(*other as usize, index)
+                });
+
+            match neighbour {
+                Some(other) => {
+                    remap.push(other);
+                    welded += 1;
+                }
+                None => {
+                    grid.entry(center).or_default().push(index as u32);
+                    remap.push(index as u32);
+                }
+            }
+        }
+
+        for triangle in self.triangles.iter_mut() {
+            triangle.vertices = triangle.vertices.map(|index| remap[index as usize]);
+        }
+
+        welded
+    }
+
+    fn attributes_match(&self, a: usize, b: usize) -> bool {
+        const EPSILON: f32 = 1e-5;
+        self.normals.as_ref().is_none_or(|n| n[a].abs_diff_eq(n[b], EPSILON))
+            && self.uvs.as_ref().is_none_or(|uv| uv[a].abs_diff_eq(uv[b], EPSILON))
+            && self.tangents.as_ref().is_none_or(|t| t[a].abs_diff_eq(t[b], EPSILON))
+            && self.colors.as_ref().is_none_or(|c| c[a].abs_diff_eq(c[b], EPSILON))
+    }
+
+    /// Maps each undirected edge to the triangles using it.
+    fn edge_triangles(&self) -> HashMap<(u32, u32), Vec<usize>> {
+        let mut edges: HashMap<_, Vec<usize>> = HashMap::new();
+        for (index, triangle) in self.triangles.iter().enumerate() {
+            for (a, b) in directed_edges(triangle) {
+                edges.entry((a.min(b), a.max(b))).or_default().push(index);
+            }
+        }
+
+        edges
+    }
+
+    /// Flips triangles so that neighbours sharing an edge traverse it in
+    /// opposite directions. Each connected part then faces the way most of
+    /// its triangles already did: outwards when the part is closed, along the
+    /// vertex normals when there are any, and otherwise by majority vote.
+    /// Returns how many triangles were flipped.
+    fn orient_triangles(&mut self) -> usize {
+        let edges = self.edge_triangles();
+        // Whether each triangle needs flipping, at first to agree with the
+        // seed of its part.
+        let mut flip = vec![false; self.triangles.len()];
+        let mut visited = vec![false; self.triangles.len()];
+
+        for seed in 0..self.triangles.len() {
+            if visited[seed] {
+                continue;
+            }
+            visited[seed] = true;
+            let mut component = vec![seed];
+            let mut closed = true;
+            let mut queue = VecDeque::from([seed]);
+            while let Some(current) = queue.pop_front() {
+                for (a, b) in directed_edges(&self.triangles[current]) {
+                    let neighbours = &edges[&(a.min(b), a.max(b))];
+                    if neighbours.len() != 2 {
+                        // Boundary and non-manifold edges give no reliable
+                        // orientation to propagate.
+                        closed = false;
+                        continue;
+                    }
+                    let neighbour = neighbours[0] + neighbours[1] - current;
+                    if self.is_back_to_back(current, neighbour) {
+                        closed = false;
+                        continue;
+                    }
+                    if visited[neighbour] {
+                        continue;
+                    }
+                    visited[neighbour] = true;
+                    let disagrees = directed_edges(&self.triangles[neighbour]).contains(&(a, b));
+                    flip[neighbour] = flip[current] != disagrees;
+                    component.push(neighbour);
+                    queue.push_back(neighbour);
+                }
+            }
+
+            // Positive when the part as oriented like the seed faces the
+            // right way.
+            let sign = |index: usize| if flip[index] { -1.0 } else { 1.0 };
+            let score: f32 = if closed {
+                component.iter().map(|&index| sign(index) * self.signed_volume(index)).sum()
+            } else {
+                match &self.normals {
+                    Some(normals) => component
+                        .iter()
+                        .map(|&index| {
+                            let triangle = &self.triangles[index];
+                            let [p1, p2, p3] = self.positions(triangle);
+                            let normal: Vec3 =
+                                triangle.vertices.iter().map(|&v| normals[v as usize]).sum();
+                            sign(index) * (p2 - p1).cross(p3 - p1).dot(normal)
+                        })
+                        .sum(),
+                    None => 0.0,
+                }
+            };
+            let flipped = component.iter().filter(|&&index| flip[index]).count();
+            let invert = score < 0.0 || (score == 0.0 && 2 * flipped > component.len());
+            if invert {
+                for &index in &component {
+                    flip[index] = !flip[index];
+                }
+            }
+        }
+
+        for (triangle, _) in self.triangles.iter_mut().zip(&flip).filter(|(_, flip)| **flip) {
+            triangle.vertices.swap(1, 2);
+        }
+
+        flip.into_iter().filter(|flip| *flip).count()
+    }
+
+    /// Whether two triangles use the same vertices, which faces meant to be
+    /// seen from both sides do with opposite windings.
+    fn is_back_to_back(&self, a: usize, b: usize) -> bool {
+        let mut a = self.triangles[a].vertices;
+        let mut b = self.triangles[b].vertices;
+        a.sort_unstable();
+        b.sort_unstable();
+        a == b
+    }
+
+    /// The signed volume of the tetrahedron between a triangle and the
+    /// origin, which sums to the volume enclosed by a closed surface.
+    fn signed_volume(&self, index: usize) -> f32 {
+        let [p1, p2, p3] = self.positions(&self.triangles[index]);
+        p1.dot(p2.cross(p3)) / 6.0
+    }
+
+    /// Compacts the vertex arrays to the vertices triangles use and returns
+    /// how many were removed.
+    fn remove_unused_vertices(&mut self) -> usize {
+        let mut remap = vec![u32::MAX; self.positions.len()];
+        let mut kept = Vec::new();
+        for triangle in self.triangles.iter_mut() {
+            for index in triangle.vertices.iter_mut() {
+                if remap[*index as usize] == u32::MAX {
+                    remap[*index as usize] = kept.len() as u32;
+                    kept.push(*index as usize);
+                }
+                *index = remap[*index as usize];
+            }
+        }
+
+        let removed = self.positions.len() - kept.len();
+        self.positions = kept.iter().map(|index| self.positions[*index]).collect();
+        compact(&mut self.normals, &kept);
+        compact(&mut self.uvs, &kept);
+        compact(&mut self.tangents, &kept);
+        compact(&mut self.colors, &kept);
+
+        removed
+    }
+}
+
+fn compact<T: Copy>(attribute: &mut Option<Vec<T>>, kept: &[usize]) {
+    if let Some(values) = attribute.as_mut() {
+        *values = kept.iter().map(|index| values[*index]).collect();
+    }
+}
+
+fn directed_edges(triangle: &Triangle) -> [(u32, u32); 3] {
+    let [a, b, c] = triangle.vertices;
+    [(a, b), (b, c), (c, a)]
+}
+
+/// A triangle is degenerate when it repeats a vertex, has non-finite corners
+/// or its area is negligible compared to its longest edge.
+pub(crate) fn is_degenerate([p1, p2, p3]: [Vec3; 3]) -> bool {
+    let longest = (p2 - p1)
+        .length_squared()
+        .max((p3 - p2).length_squared())
+        .max((p1 - p3).length_squared());
+    let area = (p2 - p1).cross(p3 - p1).length();
+
+    !area.is_finite() || area <= longest * 1e-7
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A flat strip of `quads` unit squares in the xy plane, facing `+z`.
+    fn strip(quads: u32) -> TriangleMesh {
+        let positions = (0..=quads)
+            .flat_map(|x| [Vec3::new(x as f32, 0.0, 0.0), Vec3::new(x as f32, 1.0, 0.0)])
+            .collect();
+        let triangles = (0..quads)
+            .flat_map(|x| {
+                let [a, b, c, d] = [2 * x, 2 * x + 2, 2 * x + 3, 2 * x + 1];
+                [Triangle::new([a, b, c], 0), Triangle::new([a, c, d], 0)]
+            })
+            .collect();
+        TriangleMesh::new(positions, triangles)
+    }
+
+    fn facing(mesh: &TriangleMesh) -> Vec<f32> {
+        mesh.triangles.iter().map(|triangle| mesh.face_normal(triangle).z).collect()
+    }
+
+    #[test]
+    fn open_parts_follow_the_majority() {
+        let mut mesh = strip(3);
+        mesh.triangles[0].vertices.swap(1, 2);
+        let report = mesh.cleanup(1e-6);
+
+        assert_eq!(report.flipped_triangles, 1);
+        assert_eq!(facing(&mesh), [1.0; 6]);
+    }
+
+    #[test]
+    fn open_parts_follow_vertex_normals() {
+        let mut mesh = strip(2);
+        mesh.normals = Some(vec![Vec3::NEG_Z; mesh.vertex_count()]);
+        mesh.triangles[2].vertices.swap(1, 2);
+        let report = mesh.cleanup(1e-6);
+
+        assert_eq!(report.flipped_triangles, 3);
+        assert_eq!(facing(&mesh), [-1.0; 4]);
+    }
+
+    #[test]
+    fn closed_parts_face_outwards() {
+        let positions = vec![Vec3::ZERO, Vec3::X, Vec3::Y, Vec3::Z];
+        let inwards = [[0, 1, 2], [0, 3, 1], [0, 2, 3], [1, 3, 2]];
+        let triangles = inwards.map(|vertices| Triangle::new(vertices, 0)).to_vec();
+        let mut mesh = TriangleMesh::new(positions, triangles);
+        let report = mesh.cleanup(1e-6);
+
+        assert_eq!(report.flipped_triangles, 4);
+        assert_eq!(mesh.face_normal(&mesh.triangles[0]), Vec3::NEG_Z);
+    }
+
+    #[test]
+    fn zero_tolerance_welds_identical_positions() {
+        let mut mesh = strip(1000);
+        let far = Vec3::new(1e4, 0.0, 0.0);
+        mesh.positions.extend([far, far, far + Vec3::Y]);
+        mesh.triangles.push(Triangle::new([0, 2002, 2004], 0));
+        mesh.triangles.push(Triangle::new([1, 2004, 2003], 0));
+        let report = mesh.cleanup(0.0);
+
+        assert_eq!(report.welded_vertices, 1);
+        assert_eq!(mesh.vertex_count(), 2004);
+    }
+
+    #[test]
+    fn duplicates_keep_their_winding_and_material() {
+        let mut mesh = strip(1);
+        let [first, _] = [mesh.triangles[0], mesh.triangles[1]];
+        let [a, b, c] = first.vertices;
+        mesh.triangles.extend([
+            Triangle::new([b, c, a], 0),
+            Triangle::new([a, c, b], 0),
+            Triangle::new([a, b, c], 1),
+        ]);
+        let report = mesh.cleanup(1e-6);
+
+        assert_eq!(report.duplicate_triangles, 1);
+        assert_eq!(report.flipped_triangles, 0);
+        assert_eq!(mesh.triangle_count(), 4);
+        assert_eq!(facing(&mesh), [1.0, 1.0, -1.0, 1.0]);
+    }
+}
@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+use std::f32::consts::{PI, TAU};
+
+use crate::shape::mesh::Mesh;
+use crate::shape::mesh_cleanup::is_degenerate;
+use crate::shape::sphere::Sphere;
+use crate::shape::triangle_mesh::*;
+
+/// Generated meshes are centered at the origin with +Y up, wound
+/// counter-clockwise when seen from outside, and use the same texture
+/// coordinate conventions as the analytic shapes.
+impl TriangleMesh {
+    /// A box with the given edge lengths. Every face maps the whole texture.
+    pub fn cuboid(size: Vec3) -> Self {
+        let half = size / 2.0;
+        let mut mesh = TriangleMesh::default();
+        for (normal, up) in [
+            (Vec3::X, Vec3::Y),
+            (Vec3::NEG_X, Vec3::Y),
+            (Vec3::Y, Vec3::NEG_Z),
+            (Vec3::NEG_Y, Vec3::Z),
+            (Vec3::Z, Vec3::Y),
+            (Vec3::NEG_Z, Vec3::Y),
+        ] {
+            let right = up.cross(normal);
+            mesh.append(Self::parametric(1, 1, |uv| {
+                let position = (normal + right * (uv.x * 2.0 - 1.0) + up * (1.0 - uv.y * 2.0))
+                    * half;
+                (position, normal, uv)
+            }));
+        }
+
+        mesh
+    }
+
+    pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> Self {
+        Self::parametric(segments.max(3), rings.max(2), |uv| {
+            let normal = spherical_direction(uv);
+            (normal * radius, normal, uv)
+        })
+    }
+
+    /// A sphere made by subdividing the faces of an icosahedron, which spreads
+    /// the vertices more evenly than `uv_sphere`.
+    pub fn icosphere(radius: f32, subdivisions: u32) -> Self {
+        let t = (1.0 + 5f32.sqrt()) / 2.0;
+        let mut directions: Vec<Vec3> = [
+            (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+            (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+            (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0),
+        ]
+        .iter()
+        .map(|(x, y, z)| Vec3::new(*x, *y, *z).normalize())
+        .collect();
+        let mut faces: Vec<[u32; 3]> = vec![
+            [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+            [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+            [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+            [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+        ];
+
+        for _ in 0..subdivisions {
+            let mut midpoints = HashMap::new();
+            let mut midpoint = |a: u32, b: u32| {
+                *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    let direction = (directions[a as usize] + directions[b as usize]).normalize();
+                    directions.push(direction);
+                    (directions.len() - 1) as u32
+                })
+            };
+            faces = faces
+                .iter()
+                .flat_map(|[a, b, c]| {
+                    let (ab, bc, ca) = (midpoint(*a, *b), midpoint(*b, *c), midpoint(*c, *a));
+                    [[*a, ab, ca], [*b, bc, ab], [*c, ca, bc], [ab, bc, ca]]
+                })
+                .collect();
+        }
+
+        // Triangles crossing the texture seam get their own copies of the
+        // vertices on the far side, so their coordinates do not wrap around.
+        let mut mesh = TriangleMesh::default();
+        let mut uvs: Vec<Vec2> = directions
+            .iter()
+            .map(|direction| Vec2::from(Sphere::get_uv(*direction)))
+            .collect();
+        let mut seam_vertices = HashMap::new();
+        for face in &faces {
+            let [u1, u2, u3] = face.map(|vertex| uvs[vertex as usize].x);
+            let crosses_seam = u1.max(u2).max(u3) - u1.min(u2).min(u3) > 0.5;
+            let vertices = face.map(|vertex| {
+                if crosses_seam && uvs[vertex as usize].x < 0.5 {
+                    *seam_vertices.entry(vertex).or_insert_with(|| {
+                        directions.push(directions[vertex as usize]);
+                        uvs.push(uvs[vertex as usize] + Vec2::X);
+                        (directions.len() - 1) as u32
+                    })
+                } else {
+                    vertex
+                }
+            });
+            mesh.triangles.push(Triangle::new(vertices, 0));
+        }
+
+        mesh.positions = directions.iter().map(|direction| *direction * radius).collect();
+        mesh.normals = Some(directions);
+        mesh.uvs = Some(uvs);
+
+        mesh
+    }
+
+    /// A closed cylinder along the Y axis.
+    pub fn cylinder(radius: f32, height: f32, segments: u32) -> Self {
+        let segments = segments.max(3);
+        let mut mesh = Self::parametric(segments, 1, |uv| {
+            let normal = spherical_direction(Vec2::new(uv.x, 0.5));
+            let position = normal * radius + Vec3::Y * height * (0.5 - uv.y);
+            (position, normal, uv)
+        });
+        mesh.append(Self::disk(radius, height / 2.0, Vec3::Y, segments));
+        mesh.append(Self::disk(radius, -height / 2.0, Vec3::NEG_Y, segments));
+
+        mesh
+    }
+
+    /// A closed cone along the Y axis with its apex at the top.
+    pub fn cone(radius: f32, height: f32, segments: u32) -> Self {
+        let segments = segments.max(3);
+        let slope = radius / height;
+        let mut mesh = Self::parametric(segments, 1, |uv| {
+            let direction = spherical_direction(Vec2::new(uv.x, 0.5));
+            let normal = (direction + Vec3::Y * slope).normalize();
+            let position = direction * radius * uv.y + Vec3::Y * height * (0.5 - uv.y);
+            (position, normal, uv)
+        });
+        mesh.append(Self::disk(radius, -height / 2.0, Vec3::NEG_Y, segments));
+
+        mesh
+    }
+
+    /// A torus around the Y axis. `major_radius` is the distance from the
+    /// center to the middle of the tube.
+    pub fn torus(
+        major_radius: f32,
+        minor_radius: f32,
+        major_segments: u32,
+        minor_segments: u32,
+    ) -> Self {
+        Self::parametric(major_segments.max(3), minor_segments.max(3), |uv| {
+            let outward = spherical_direction(Vec2::new(uv.x, 0.5));
+            let angle = uv.y * TAU;
+            let normal = outward * angle.cos() + Vec3::Y * angle.sin();
+            (outward * major_radius + normal * minor_radius, normal, uv)
+        })
+    }
+
+    /// A flat grid in the XZ plane facing +Y.
+    pub fn plane_grid(size: Vec2, columns: u32, rows: u32) -> Self {
+        Self::heightfield(size, columns, rows, |_, _| 0.0)
+    }
+
+    /// A grid in the XZ plane whose vertices are raised to `height(x, z)`.
+    /// Normals come from central differences of the function.
+    pub fn heightfield(
+        size: Vec2,
+        columns: u32,
+        rows: u32,
+        height: impl Fn(f32, f32) -> f32,
+    ) -> Self {
+        let (columns, rows) = (columns.max(1), rows.max(1));
+        let step = size / Vec2::new(columns as f32, rows as f32);
+        Self::parametric(columns, rows, |uv| {
+            let x = (uv.x - 0.5) * size.x;
+            let z = (uv.y - 0.5) * size.y;
+            let dx = (height(x + step.x / 2.0, z) - height(x - step.x / 2.0, z)) / step.x;
+            let dz = (height(x, z + step.y / 2.0) - height(x, z - step.y / 2.0)) / step.y;
+            let normal = Vec3::new(-dx, 1.0, -dz).normalize();
+            (Vec3::new(x, height(x, z), z), normal, uv)
+        })
+    }
+
+    /// A disk at height `y` facing `normal`, used for caps.
+    fn disk(radius: f32, y: f32, normal: Vec3, segments: u32) -> Self {
+        Self::parametric(segments, 1, |uv| {
+            let direction = spherical_direction(Vec2::new(uv.x, 0.5));
+            let position = direction * radius * uv.y + Vec3::Y * y;
+            let uv = Vec2::new(direction.x, direction.z) * uv.y * 0.5 + 0.5;
+            (position, normal, uv)
+        })
+    }
+
+    /// Tessellates a parametric surface into a grid of `columns` by `rows`
+    /// quads. `surface` maps coordinates in `0..=1` to a position, normal and
+    /// texture coordinate. Triangles are wound to face their vertex normals
+    /// and degenerate triangles, such as those at poles, are skipped.
+    fn parametric(
+        columns: u32,
+        rows: u32,
+        surface: impl Fn(Vec2) -> (Vec3, Vec3, Vec2),
+    ) -> Self {
+        let mut mesh = TriangleMesh::default();
+        let mut normals = Vec::new();
+        let mut uvs = Vec::new();
+        for row in 0..=rows {
+            for column in 0..=columns {
+                let uv = Vec2::new(column as f32 / columns as f32, row as f32 / rows as f32);
+                let (position, normal, uv) = surface(uv);
+                mesh.positions.push(position);
+                normals.push(normal);
+                uvs.push(uv);
+            }
+        }
+
+        let index = |column: u32, row: u32| row * (columns + 1) + column;
+        for row in 0..rows {
+            for column in 0..columns {
+                let a = index(column, row);
+                let b = index(column + 1, row);
+                let c = index(column + 1, row + 1);
+                let d = index(column, row + 1);
+                for mut vertices in [[a, b, c], [a, c, d]] {
+                    let [p1, p2, p3] = vertices.map(|vertex| mesh.positions[vertex as usize]);
+                    if is_degenerate([p1, p2, p3]) {
+                        continue;
+                    }
+                    let face_normal = (p2 - p1).cross(p3 - p1);
+                    let normal: Vec3 = vertices.iter().map(|vertex| normals[*vertex as usize]).sum();
+                    if face_normal.dot(normal) < 0.0 {
+                        vertices.swap(1, 2);
+                    }
+                    mesh.triangles.push(Triangle::new(vertices, 0));
+                }
+            }
+        }
+        mesh.normals = Some(normals);
+        mesh.uvs = Some(uvs);
+
+        mesh
+    }
+}
+
+/// The direction of the point with texture coordinates `uv` on a unit sphere.
+fn spherical_direction(uv: Vec2) -> Vec3 {
+    let azimuth = (uv.x - 0.5) * TAU;
+    let polar = uv.y * PI;
+    // Measured from the nearer pole, so both poles are exactly on the axis.
+    let radius = (uv.y.min(1.0 - uv.y) * PI).sin();
+    Vec3::new(radius * azimuth.sin(), polar.cos(), radius * azimuth.cos())
+}
+
+impl Mesh {
+    pub fn cuboid(size: Vec3, material: Material) -> Self {
+        Self::from_geometry(TriangleMesh::cuboid(size), vec![material])
+    }
+
+    pub fn uv_sphere(radius: f32, segments: u32, rings: u32, material: Material) -> Self {
+        Self::from_geometry(TriangleMesh::uv_sphere(radius, segments, rings), vec![material])
+    }
+
+    pub fn icosphere(radius: f32, subdivisions: u32, material: Material) -> Self {
+        Self::from_geometry(TriangleMesh::icosphere(radius, subdivisions), vec![material])
+    }
+
+    pub fn cylinder(radius: f32, height: f32, segments: u32, material: Material) -> Self {
+        Self::from_geometry(TriangleMesh::cylinder(radius, height, segments), vec![material])
+    }
+
+    pub fn cone(radius: f32, height: f32, segments: u32, material: Material) -> Self {
+        Self::from_geometry(TriangleMesh::cone(radius, height, segments), vec![material])
+    }
+
+    pub fn torus(
+        major_radius: f32,
+        minor_radius: f32,
+        major_segments: u32,
+        minor_segments: u32,
+        material: Material,
+    ) -> Self {
+        let geometry =
+            TriangleMesh::torus(major_radius, minor_radius, major_segments, minor_segments);
+        Self::from_geometry(geometry, vec![material])
+    }
+
+    pub fn plane_grid(size: Vec2, columns: u32, rows: u32, material: Material) -> Self {
+        Self::from_geometry(TriangleMesh::plane_grid(size, columns, rows), vec![material])
+    }
+
+    pub fn heightfield(
+        size: Vec2,
+        columns: u32,
+        rows: u32,
+        height: impl Fn(f32, f32) -> f32,
+        material: Material,
+    ) -> Self {
+        let geometry = TriangleMesh::heightfield(size, columns, rows, height);
+        Self::from_geometry(geometry, vec![material])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether every triangle faces away from the center of the mesh, which
+    /// holds for the convex shapes generated here.
+    fn faces_outwards(mesh: &TriangleMesh) -> bool {
+        mesh.triangles.iter().all(|triangle| {
+            let [p1, p2, p3] = mesh.positions(triangle);
+            (p2 - p1).cross(p3 - p1).dot(triangle.centroid(&mesh.positions)) > 0.0
+        })
+    }
+
+    #[test]
+    fn skips_only_the_triangles_at_the_poles() {
+        for radius in [1e-4, 1.0, 1e4] {
+            let mesh = TriangleMesh::uv_sphere(radius, 16, 8);
+            assert_eq!(mesh.vertex_count(), 17 * 9);
+            assert_eq!(mesh.triangle_count(), 16 * 8 * 2 - 16 * 2);
+            assert!(faces_outwards(&mesh));
+        }
+    }
+
+    #[test]
+    fn winds_every_generated_shape_outwards() {
+        let cuboid = TriangleMesh::cuboid(Vec3::new(1e-3, 2e-3, 3e-3));
+        assert_eq!(cuboid.triangle_count(), 12);
+        assert!(faces_outwards(&cuboid));
+
+        for mesh in [
+            TriangleMesh::icosphere(1e-3, 2),
+            TriangleMesh::cylinder(1e-3, 2e-3, 12),
+            TriangleMesh::cone(1e-3, 2e-3, 12),
+        ] {
+            assert!(mesh.triangle_count() > 0);
+            assert!(faces_outwards(&mesh));
+        }
+    }
+}
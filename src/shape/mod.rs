@@ -1,54 +1,158 @@
+pub mod cone;
 pub mod constant_medium;
+pub mod csg;
+pub mod cuboid;
+pub mod cylinder;
+pub mod disk;
+pub mod environment_light;
+pub mod heterogeneous_medium;
 pub mod triangle;
+pub mod triangle_mesh;
 pub mod hittable;
 pub mod mesh;
+pub mod mesh_cleanup;
+pub mod mesh_generators;
+pub mod object_settings;
 pub mod plane;
 pub mod quadrilateral;
+pub mod sdf;
 pub mod sphere;
+pub mod sun;
+pub mod torus;
 
 pub use crate::shape::hittable::*;
+pub use crate::shape::object_settings::Visibility;
+use crate::texture::Texture;
 
 #[derive(Clone)]
 pub enum Shape {
     Quadrilateral(quadrilateral::Quadrilateral),
-    SmokeCube(constant_medium::SmokeCube),
+    ConstantMedium(constant_medium::ConstantMedium),
     Sphere(sphere::Sphere),
     Plane(plane::Plane),
     Mesh(mesh::Mesh),
+    Cuboid(cuboid::Cuboid),
+    Disk(disk::Disk),
+    Cylinder(cylinder::Cylinder),
+    Cone(cone::Cone),
+    Torus(torus::Torus),
+    Csg(csg::Csg),
+    Sdf(sdf::SdfShape),
+    HeterogeneousMedium(heterogeneous_medium::HeterogeneousMedium),
+    Sun(sun::Sun),
+    EnvironmentLight(environment_light::EnvironmentLight),
+    ObjectSettings(object_settings::ObjectSettings),
     List(Vec<Shape>),
 }
 
 impl Hittable for Shape {
-    fn hits(&self, ray: &Ray, interval: Range<f32>) -> Option<HitRecord> {
+    fn hits(&self, ray: &Ray, interval: Range<f32>) -> Option<HitRecord<'_>> {
         match self {
             Shape::Quadrilateral(quadrilateral) => quadrilateral.hits(ray, interval),
-            Shape::SmokeCube(smoke_cube) => smoke_cube.hits(ray, interval),
+            Shape::ConstantMedium(medium) => medium.hits(ray, interval),
             Shape::Sphere(sphere) => sphere.hits(ray, interval),
             Shape::List(shapes) => shapes.hits(ray, interval),
             Shape::Plane(plane) => plane.hits(ray, interval),
             Shape::Mesh(mesh) => mesh.hits(ray, interval),
+            Shape::Cuboid(cuboid) => cuboid.hits(ray, interval),
+            Shape::Disk(disk) => disk.hits(ray, interval),
+            Shape::Cylinder(cylinder) => cylinder.hits(ray, interval),
+            Shape::Cone(cone) => cone.hits(ray, interval),
+            Shape::Torus(torus) => torus.hits(ray, interval),
+            Shape::Csg(csg) => csg.hits(ray, interval),
+            Shape::Sdf(sdf) => sdf.hits(ray, interval),
+            Shape::HeterogeneousMedium(medium) => medium.hits(ray, interval),
+            Shape::Sun(sun) => sun.hits(ray, interval),
+            Shape::EnvironmentLight(light) => light.hits(ray, interval),
+            Shape::ObjectSettings(settings) => settings.hits(ray, interval),
+        }
+    }
+
+    fn hits_all(&self, ray: &Ray, interval: Range<f32>) -> Vec<HitRecord<'_>> {
+        match self {
+            Shape::Quadrilateral(quadrilateral) => quadrilateral.hits_all(ray, interval),
+            Shape::ConstantMedium(medium) => medium.hits_all(ray, interval),
+            Shape::Sphere(sphere) => sphere.hits_all(ray, interval),
+            Shape::List(shapes) => shapes.hits_all(ray, interval),
+            Shape::Plane(plane) => plane.hits_all(ray, interval),
+            Shape::Mesh(mesh) => mesh.hits_all(ray, interval),
+            Shape::Cuboid(cuboid) => cuboid.hits_all(ray, interval),
+            Shape::Disk(disk) => disk.hits_all(ray, interval),
+            Shape::Cylinder(cylinder) => cylinder.hits_all(ray, interval),
+            Shape::Cone(cone) => cone.hits_all(ray, interval),
+            Shape::Torus(torus) => torus.hits_all(ray, interval),
+            Shape::Csg(csg) => csg.hits_all(ray, interval),
+            Shape::Sdf(sdf) => sdf.hits_all(ray, interval),
+            Shape::HeterogeneousMedium(medium) => medium.hits_all(ray, interval),
+            Shape::Sun(sun) => sun.hits_all(ray, interval),
+            Shape::EnvironmentLight(light) => light.hits_all(ray, interval),
+            Shape::ObjectSettings(settings) => settings.hits_all(ray, interval),
+        }
+    }
+
+    fn transmittance(&self, ray: &Ray, interval: Range<f32>) -> f32 {
+        match self {
+            Shape::Quadrilateral(quadrilateral) => quadrilateral.transmittance(ray, interval),
+            Shape::ConstantMedium(medium) => medium.transmittance(ray, interval),
+            Shape::Sphere(sphere) => sphere.transmittance(ray, interval),
+            Shape::List(shapes) => shapes.transmittance(ray, interval),
+            Shape::Plane(plane) => plane.transmittance(ray, interval),
+            Shape::Mesh(mesh) => mesh.transmittance(ray, interval),
+            Shape::Cuboid(cuboid) => cuboid.transmittance(ray, interval),
+            Shape::Disk(disk) => disk.transmittance(ray, interval),
+            Shape::Cylinder(cylinder) => cylinder.transmittance(ray, interval),
+            Shape::Cone(cone) => cone.transmittance(ray, interval),
+            Shape::Torus(torus) => torus.transmittance(ray, interval),
+            Shape::Csg(csg) => csg.transmittance(ray, interval),
+            Shape::Sdf(sdf) => sdf.transmittance(ray, interval),
+            Shape::HeterogeneousMedium(medium) => medium.transmittance(ray, interval),
+            Shape::Sun(sun) => sun.transmittance(ray, interval),
+            Shape::EnvironmentLight(light) => light.transmittance(ray, interval),
+            Shape::ObjectSettings(settings) => settings.transmittance(ray, interval),
         }
     }
 
     fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f32 {
         match self {
             Shape::Quadrilateral(quadrilateral) => quadrilateral.pdf_value(origin, direction),
-            Shape::SmokeCube(smoke_cube) => smoke_cube.pdf_value(origin, direction),
+            Shape::ConstantMedium(medium) => medium.pdf_value(origin, direction),
             Shape::Sphere(sphere) => sphere.pdf_value(origin, direction),
             Shape::List(shapes) => shapes.pdf_value(origin, direction),
             Shape::Plane(plane) => plane.pdf_value(origin, direction),
             Shape::Mesh(mesh) => mesh.pdf_value(origin, direction),
+            Shape::Cuboid(cuboid) => cuboid.pdf_value(origin, direction),
+            Shape::Disk(disk) => disk.pdf_value(origin, direction),
+            Shape::Cylinder(cylinder) => cylinder.pdf_value(origin, direction),
+            Shape::Cone(cone) => cone.pdf_value(origin, direction),
+            Shape::Torus(torus) => torus.pdf_value(origin, direction),
+            Shape::Csg(csg) => csg.pdf_value(origin, direction),
+            Shape::Sdf(sdf) => sdf.pdf_value(origin, direction),
+            Shape::HeterogeneousMedium(medium) => medium.pdf_value(origin, direction),
+            Shape::Sun(sun) => sun.pdf_value(origin, direction),
+            Shape::EnvironmentLight(light) => light.pdf_value(origin, direction),
+            Shape::ObjectSettings(settings) => settings.pdf_value(origin, direction),
         }
     }
 
     fn random(&self, origin: &Vec3) -> Vec3 {
         match self {
             Shape::Quadrilateral(quadrilateral) => quadrilateral.random(origin),
-            Shape::SmokeCube(smoke_cube) => smoke_cube.random(origin),
+            Shape::ConstantMedium(medium) => medium.random(origin),
             Shape::Sphere(sphere) => sphere.random(origin),
             Shape::List(shapes) => shapes.random(origin),
             Shape::Plane(plane) => plane.random(origin),
             Shape::Mesh(mesh) => mesh.random(origin),
+            Shape::Cuboid(cuboid) => cuboid.random(origin),
+            Shape::Disk(disk) => disk.random(origin),
+            Shape::Cylinder(cylinder) => cylinder.random(origin),
+            Shape::Cone(cone) => cone.random(origin),
+            Shape::Torus(torus) => torus.random(origin),
+            Shape::Csg(csg) => csg.random(origin),
+            Shape::Sdf(sdf) => sdf.random(origin),
+            Shape::HeterogeneousMedium(medium) => medium.random(origin),
+            Shape::Sun(sun) => sun.random(origin),
+            Shape::EnvironmentLight(light) => light.random(origin),
+            Shape::ObjectSettings(settings) => settings.random(origin),
         }
     }
 }
@@ -80,15 +184,134 @@ impl Shape {
         ))
     }
 
+    pub fn cuboid(a: Vec3, b: Vec3, material: Material) -> Self {
+        Shape::Cuboid(cuboid::Cuboid::from_corners(a, b, material))
+    }
+
+    pub fn disk(center: Vec3, normal: Vec3, radius: f32, material: Material) -> Self {
+        Shape::Disk(disk::Disk::new(center, normal, radius, material))
+    }
+
+    pub fn annulus(
+        center: Vec3,
+        normal: Vec3,
+        inner_radius: f32,
+        radius: f32,
+        material: Material,
+    ) -> Self {
+        Shape::Disk(disk::Disk::annulus(center, normal, inner_radius, radius, material))
+    }
+
+    pub fn cylinder(base: Vec3, top: Vec3, radius: f32, material: Material) -> Self {
+        Shape::Cylinder(cylinder::Cylinder::new(base, top, radius, true, material))
+    }
+
+    pub fn open_cylinder(base: Vec3, top: Vec3, radius: f32, material: Material) -> Self {
+        Shape::Cylinder(cylinder::Cylinder::new(base, top, radius, false, material))
+    }
+
+    pub fn cone(base: Vec3, apex: Vec3, radius: f32, material: Material) -> Self {
+        Shape::Cone(cone::Cone::new(base, apex, radius, true, material))
+    }
+
+    pub fn open_cone(base: Vec3, apex: Vec3, radius: f32, material: Material) -> Self {
+        Shape::Cone(cone::Cone::new(base, apex, radius, false, material))
+    }
+
+    pub fn torus(
+        center: Vec3,
+        axis: Vec3,
+        major_radius: f32,
+        minor_radius: f32,
+        material: Material,
+    ) -> Self {
+        Shape::Torus(torus::Torus::new(center, axis, major_radius, minor_radius, material))
+    }
+
+    pub fn union(left: Shape, right: Shape) -> Self {
+        Shape::Csg(csg::Csg::new(csg::Operation::Union, left, right))
+    }
+
+    pub fn intersection(left: Shape, right: Shape) -> Self {
+        Shape::Csg(csg::Csg::new(csg::Operation::Intersection, left, right))
+    }
+
+    /// The part of `left` outside of `right`.
+    pub fn difference(left: Shape, right: Shape) -> Self {
+        Shape::Csg(csg::Csg::new(csg::Operation::Difference, left, right))
+    }
+
+    pub fn sdf(sdf: sdf::Sdf, material: Material) -> Self {
+        Shape::Sdf(sdf::SdfShape::new(sdf, material))
+    }
+
+    /// A medium filling `boundary` with a density from a texture whose values
+    /// do not exceed `max_density`.
+    pub fn heterogeneous_medium(boundary: Shape, density: Texture, max_density: f32) -> Self {
+        Shape::HeterogeneousMedium(heterogeneous_medium::HeterogeneousMedium::new(
+            boundary,
+            density,
+            max_density,
+        ))
+    }
+
+    /// Smoke or fog of uniform density filling `boundary`.
+    pub fn constant_medium(boundary: Shape, density: f32, albedo: Vec3) -> Self {
+        Shape::ConstantMedium(constant_medium::ConstantMedium::new(boundary, density, albedo))
+    }
+
+    /// A constant medium filling a mesh, the same as `constant_medium`.
     pub fn smoke_box(boundary: mesh::Mesh, density: f32, albedo: Vec3) -> Self {
-        Shape::SmokeCube(constant_medium::SmokeCube::new(boundary, density, albedo))
+        Shape::constant_medium(Shape::Mesh(boundary), density, albedo)
+    }
+
+    /// A constant medium filling a box, the same as `constant_medium`.
+    pub fn smoke_cuboid(boundary: cuboid::Cuboid, density: f32, albedo: Vec3) -> Self {
+        Shape::constant_medium(Shape::Cuboid(boundary), density, albedo)
     }
 
     pub fn mesh(path_to_model: &str, material: Material) -> Self {
         Shape::Mesh(mesh::Mesh::new(path_to_model, material))
     }
 
+    pub fn try_mesh(path_to_model: &str, material: Material) -> crate::error::Result<Self> {
+        mesh::Mesh::load(path_to_model, material).map(Shape::Mesh)
+    }
+
     pub fn list(shapes: Vec<Shape>) -> Self {
         Shape::List(shapes)
     }
+
+    /// Hides the shape from some kinds of rays.
+    pub fn with_visibility(self, visibility: Visibility) -> Self {
+        let mut settings = self.into_object_settings();
+        settings.visibility = visibility;
+        Shape::ObjectSettings(settings)
+    }
+
+    /// Puts the shape on the light channels in the bit mask `channels`.
+    pub fn with_light_channels(self, channels: u32) -> Self {
+        let mut settings = self.into_object_settings();
+        settings.light_channels = channels;
+        Shape::ObjectSettings(settings)
+    }
+
+    /// The light channels the shape lights when it emits, which for a list
+    /// are those of any of its shapes.
+    pub fn light_channels(&self) -> u32 {
+        match self {
+            Shape::ObjectSettings(settings) => settings.light_channels,
+            Shape::List(shapes) => shapes
+                .iter()
+                .fold(0, |channels, shape| channels | shape.light_channels()),
+            _ => DEFAULT_LIGHT_CHANNELS,
+        }
+    }
+
+        fn into_object_settings(self) -> object_settings::ObjectSettings {
+        match self {
+            Shape::ObjectSettings(settings) => settings,
+            shape => object_settings::ObjectSettings::new(shape),
+        }
+    }
 }
@@ -0,0 +1,103 @@
+pub use crate::shape::hittable::*;
+use crate::shape::Shape;
+
+/// The kinds of rays an object shows up for. A hidden object is passed
+/// straight through, so an object invisible to the camera still shows in
+/// mirrors and casts shadows unless those are turned off too.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Visibility {
+    pub camera: bool,
+    /// Seen by light bouncing off diffuse surfaces and media.
+    pub diffuse: bool,
+    /// Seen in mirrors and through glass.
+    pub specular: bool,
+    /// Blocks the shadow rays of `Scene::lights` and the directions aimed at
+    /// `Scene::light` when it is sampled.
+    pub shadow: bool,
+}
+
+impl Default for Visibility {
+    fn default() -> Self {
+        Self {
+            camera: true,
+            diffuse: true,
+            specular: true,
+            shadow: true,
+        }
+    }
+}
+
+impl Visibility {
+    pub fn allows(&self, kind: RayKind) -> bool {
+        match kind {
+            RayKind::Camera => self.camera,
+            RayKind::Diffuse => self.diffuse,
+            RayKind::Specular => self.specular,
+            RayKind::Shadow => self.shadow,
+        }
+    }
+}
+
+/// Per-object controls for art direction around any shape, made with
+/// `Shape::with_visibility` and `Shape::with_light_channels`.
+#[derive(Clone)]
+pub struct ObjectSettings {
+    pub shape: Box<Shape>,
+    pub visibility: Visibility,
+    /// A bit mask of light channels. The object is only lit by lights and
+    /// emitters sharing a channel with it, and when it emits itself it only
+    /// lights objects sharing one.
+    pub light_channels: u32,
+}
+
+impl ObjectSettings {
+    pub fn new(shape: Shape) -> Self {
+        Self {
+            shape: Box::new(shape),
+            visibility: Visibility::default(),
+            light_channels: DEFAULT_LIGHT_CHANNELS,
+        }
+    }
+}
+
+impl Hittable for ObjectSettings {
+    fn hits(&self, ray: &Ray, interval: Range<f32>) -> Option<HitRecord<'_>> {
+        if !self.visibility.allows(ray.kind) {
+            return None;
+        }
+
+        let mut hit_record = self.shape.hits(ray, interval)?;
+        hit_record.light_channels = self.light_channels;
+        Some(hit_record)
+    }
+
+    fn hits_all(&self, ray: &Ray, interval: Range<f32>) -> Vec<HitRecord<'_>> {
+        if !self.visibility.allows(ray.kind) {
+            return Vec::new();
+        }
+
+        let mut hit_records = self.shape.hits_all(ray, interval);
+        for hit_record in &mut hit_records {
+            hit_record.light_channels = self.light_channels;
+        }
+        hit_records
+    }
+
+    fn transmittance(&self, ray: &Ray, interval: Range<f32>) -> f32 {
+        if !self.visibility.allows(ray.kind) {
+            return 1.0;
+        }
+
+        self.shape.transmittance(ray, interval)
+    }
+
+    // Sampling the object as a light goes to the shape itself, whatever
+    // rays it is hidden from.
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        self.shape.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        self.shape.random(origin)
+    }
+}
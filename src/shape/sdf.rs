@@ -0,0 +1,443 @@
+use glam::{BVec3, Quat, Vec2};
+use std::sync::Arc;
+
+pub use crate::shape::hittable::*;
+use crate::shape::sphere::Sphere;
+
+/// A signed distance function, negative inside the surface, built from
+/// primitives centered at the origin and operations on them.
+#[derive(Clone)]
+pub enum Sdf {
+    Sphere(f32),
+    Cuboid(Vec3),
+    /// A torus around the Y axis with its major and minor radius.
+    Torus(f32, f32),
+    /// A capped cylinder along the Y axis with its radius and half height.
+    Cylinder(f32, f32),
+    Capsule(Vec3, Vec3, f32),
+    Translate(Vec3, Box<Sdf>),
+    Rotate(Quat, Box<Sdf>),
+    Scale(f32, Box<Sdf>),
+    Round(f32, Box<Sdf>),
+    Union(Box<Sdf>, Box<Sdf>),
+    Intersection(Box<Sdf>, Box<Sdf>),
+    Subtraction(Box<Sdf>, Box<Sdf>),
+    SmoothUnion(Box<Sdf>, Box<Sdf>, f32),
+    SmoothIntersection(Box<Sdf>, Box<Sdf>, f32),
+    SmoothSubtraction(Box<Sdf>, Box<Sdf>, f32),
+    /// Infinite copies every `period` along the axes where it is not zero.
+    Repeat(Vec3, Box<Sdf>),
+    /// Rotation around the Y axis by `rate` radians per unit of height.
+    Twist(f32, Box<Sdf>),
+    Function(Arc<dyn Fn(Vec3) -> f32 + Send + Sync>),
+}
+
+impl Sdf {
+    pub fn sphere(radius: f32) -> Self {
+        Sdf::Sphere(radius)
+    }
+
+    pub fn cuboid(size: Vec3) -> Self {
+        Sdf::Cuboid(size.abs() / 2.0)
+    }
+
+    pub fn torus(major_radius: f32, minor_radius: f32) -> Self {
+        Sdf::Torus(major_radius, minor_radius)
+    }
+
+    pub fn cylinder(radius: f32, height: f32) -> Self {
+        Sdf::Cylinder(radius, height / 2.0)
+    }
+
+    pub fn capsule(a: Vec3, b: Vec3, radius: f32) -> Self {
+        Sdf::Capsule(a, b, radius)
+    }
+
+    /// A distance given by a closure. It should not overestimate the distance
+    /// to the surface, and the shape needs explicit bounds to be traced.
+    pub fn function(distance: impl Fn(Vec3) -> f32 + Send + Sync + 'static) -> Self {
+        Sdf::Function(Arc::new(distance))
+    }
+
+    pub fn translate(self, offset: Vec3) -> Self {
+        Sdf::Translate(offset, Box::new(self))
+    }
+
+    pub fn rotate(self, rotation: Quat) -> Self {
+        Sdf::Rotate(rotation, Box::new(self))
+    }
+
+    /// Scales by the size of `scale`, which must not be zero. A negative
+    /// scale mirrors through the origin rather than turning the shape inside
+    /// out.
+    pub fn scale(self, scale: f32) -> Self {
+        assert!(scale != 0.0, "an SDF cannot be scaled by zero");
+        Sdf::Scale(scale.abs(), Box::new(self))
+    }
+
+    pub fn round(self, radius: f32) -> Self {
+        Sdf::Round(radius, Box::new(self))
+    }
+
+    pub fn union(self, other: Sdf) -> Self {
+        Sdf::Union(Box::new(self), Box::new(other))
+    }
+
+    pub fn intersection(self, other: Sdf) -> Self {
+        Sdf::Intersection(Box::new(self), Box::new(other))
+    }
+
+    pub fn subtraction(self, other: Sdf) -> Self {
+        Sdf::Subtraction(Box::new(self), Box::new(other))
+    }
+
+    /// A union blending the surfaces where they are closer than `smoothness`.
+    pub fn smooth_union(self, other: Sdf, smoothness: f32) -> Self {
+        Sdf::SmoothUnion(Box::new(self), Box::new(other), smoothness)
+    }
+
+    pub fn smooth_intersection(self, other: Sdf, smoothness: f32) -> Self {
+        Sdf::SmoothIntersection(Box::new(self), Box::new(other), smoothness)
+    }
+
+    pub fn smooth_subtraction(self, other: Sdf, smoothness: f32) -> Self {
+        Sdf::SmoothSubtraction(Box::new(self), Box::new(other), smoothness)
+    }
+
+    pub fn repeat(self, period: Vec3) -> Self {
+        Sdf::Repeat(period, Box::new(self))
+    }
+
+    pub fn twist(self, rate: f32) -> Self {
+        Sdf::Twist(rate, Box::new(self))
+    }
+
+    pub fn distance(&self, p: Vec3) -> f32 {
+        match self {
+            Sdf::Sphere(radius) => p.length() - radius,
+            Sdf::Cuboid(half_size) => {
+                let q = p.abs() - *half_size;
+                q.max(Vec3::ZERO).length() + q.max_element().min(0.0)
+            }
+            Sdf::Torus(major_radius, minor_radius) => {
+                let q = Vec2::new(p.x.hypot(p.z) - major_radius, p.y);
+                q.length() - minor_radius
+            }
+            Sdf::Cylinder(radius, half_height) => {
+                let d = Vec2::new(p.x.hypot(p.z) - radius, p.y.abs() - half_height);
+                d.max_element().min(0.0) + d.max(Vec2::ZERO).length()
+            }
+            Sdf::Capsule(a, b, radius) => {
+                let (pa, ba) = (p - *a, *b - *a);
+                let h = (pa.dot(ba) / ba.length_squared()).clamp(0.0, 1.0);
+                (pa - ba * h).length() - radius
+            }
+            Sdf::Translate(offset, sdf) => sdf.distance(p - *offset),
+            Sdf::Rotate(rotation, sdf) => sdf.distance(rotation.inverse() * p),
+            Sdf::Scale(scale, sdf) => sdf.distance(p / *scale) * scale.abs(),
+            Sdf::Round(radius, sdf) => sdf.distance(p) - radius,
+            Sdf::Union(a, b) => a.distance(p).min(b.distance(p)),
+            Sdf::Intersection(a, b) => a.distance(p).max(b.distance(p)),
+            Sdf::Subtraction(a, b) => a.distance(p).max(-b.distance(p)),
+            Sdf::SmoothUnion(a, b, k) => {
+                let (a, b) = (a.distance(p), b.distance(p));
+                let h = (0.5 + 0.5 * (b - a) / k).clamp(0.0, 1.0);
+                b + (a - b) * h - k * h * (1.0 - h)
+            }
+            Sdf::SmoothIntersection(a, b, k) => {
+                let (a, b) = (a.distance(p), b.distance(p));
+                let h = (0.5 - 0.5 * (b - a) / k).clamp(0.0, 1.0);
+                b + (a - b) * h + k * h * (1.0 - h)
+            }
+            Sdf::SmoothSubtraction(a, b, k) => {
+                let (a, b) = (a.distance(p), -b.distance(p));
+                let h = (0.5 - 0.5 * (b - a) / k).clamp(0.0, 1.0);
+                b + (a - b) * h + k * h * (1.0 - h)
+            }
+            Sdf::Repeat(period, sdf) => {
+                let repeated = period.cmpne(Vec3::ZERO);
+                let cell = Vec3::select(repeated, (p / *period).round(), Vec3::ZERO);
+                sdf.distance(p - *period * cell)
+            }
+            Sdf::Twist(rate, sdf) => {
+                let (sin, cos) = (rate * p.y).sin_cos();
+                sdf.distance(Vec3::new(cos * p.x + sin * p.z, p.y, cos * p.z - sin * p.x))
+            }
+            Sdf::Function(distance) => distance(p),
+        }
+    }
+
+    /// The gradient of the distance, estimated with four samples at the
+    /// corners of a tetrahedron `h` away from `p`.
+    pub fn normal(&self, p: Vec3, h: f32) -> Vec3 {
+        [
+            Vec3::new(1.0, -1.0, -1.0),
+            Vec3::new(-1.0, -1.0, 1.0),
+            Vec3::new(-1.0, 1.0, -1.0),
+            Vec3::ONE,
+        ]
+        .into_iter()
+        .map(|k| k * self.distance(p + k * h))
+        .sum::<Vec3>()
+        .normalize_or_zero()
+    }
+
+    /// A box containing the surface, infinite where it cannot be bounded.
+    pub fn bounds(&self) -> (Vec3, Vec3) {
+        let everywhere = (Vec3::NEG_INFINITY, Vec3::INFINITY);
+        match self {
+            Sdf::Sphere(radius) => (Vec3::splat(-radius), Vec3::splat(*radius)),
+            Sdf::Cuboid(half_size) => (-*half_size, *half_size),
+            Sdf::Torus(major_radius, minor_radius) => {
+                let radius = major_radius + minor_radius;
+                let extent = Vec3::new(radius, *minor_radius, radius);
+                (-extent, extent)
+            }
+            Sdf::Cylinder(radius, half_height) => {
+                let extent = Vec3::new(*radius, *half_height, *radius);
+                (-extent, extent)
+            }
+            Sdf::Capsule(a, b, radius) => (a.min(*b) - *radius, a.max(*b) + *radius),
+            Sdf::Translate(offset, sdf) => {
+                let (min, max) = sdf.bounds();
+                (min + *offset, max + *offset)
+            }
+            Sdf::Rotate(rotation, sdf) => {
+                let (min, max) = sdf.bounds();
+                if !(min.is_finite() && max.is_finite()) {
+                    return everywhere;
+                }
+                (0..8).fold((Vec3::INFINITY, Vec3::NEG_INFINITY), |(low, high), corner| {
+                    let mask = BVec3::new(corner & 1 != 0, corner & 2 != 0, corner & 4 != 0);
+                    let corner = *rotation * Vec3::select(mask, max, min);
+                    (low.min(corner), high.max(corner))
+                })
+            }
+            Sdf::Scale(scale, sdf) => {
+                let (min, max) = sdf.bounds();
+                let (min, max) = (min * *scale, max * *scale);
+                (min.min(max), min.max(max))
+            }
+            Sdf::Round(radius, sdf) => {
+                let (min, max) = sdf.bounds();
+                (min - *radius, max + *radius)
+            }
+            Sdf::Union(a, b) => union_bounds(a.bounds(), b.bounds(), 0.0),
+            Sdf::SmoothUnion(a, b, k) => union_bounds(a.bounds(), b.bounds(), k / 4.0),
+            Sdf::Intersection(a, b) | Sdf::SmoothIntersection(a, b, _) => {
+                let ((a_min, a_max), (b_min, b_max)) = (a.bounds(), b.bounds());
+                (a_min.max(b_min), a_max.min(b_max))
+            }
+            Sdf::Subtraction(a, _) | Sdf::SmoothSubtraction(a, _, _) => a.bounds(),
+            Sdf::Repeat(period, sdf) => {
+                let (min, max) = sdf.bounds();
+                let repeated = period.cmpne(Vec3::ZERO);
+                (
+                    Vec3::select(repeated, Vec3::NEG_INFINITY, min),
+                    Vec3::select(repeated, Vec3::INFINITY, max),
+                )
+            }
+            Sdf::Twist(_, sdf) => {
+                let (min, max) = sdf.bounds();
+                let radius = radius_around_y(min, max);
+                (
+                    Vec3::new(-radius, min.y, -radius),
+                    Vec3::new(radius, max.y, radius),
+                )
+            }
+            Sdf::Function(_) => everywhere,
+        }
+    }
+
+    /// How much faster than one unit per unit of distance the function can
+    /// change, which bounds how far the tracer can safely step.
+    fn lipschitz(&self) -> f32 {
+        match self {
+            Sdf::Translate(_, sdf)
+            | Sdf::Rotate(_, sdf)
+            | Sdf::Scale(_, sdf)
+            | Sdf::Round(_, sdf)
+            | Sdf::Repeat(_, sdf) => sdf.lipschitz(),
+            Sdf::Union(a, b)
+            | Sdf::Intersection(a, b)
+            | Sdf::Subtraction(a, b)
+            | Sdf::SmoothUnion(a, b, _)
+            | Sdf::SmoothIntersection(a, b, _)
+            | Sdf::SmoothSubtraction(a, b, _) => a.lipschitz().max(b.lipschitz()),
+            Sdf::Twist(rate, sdf) => {
+                let (min, max) = sdf.bounds();
+                let stretch = (rate * radius_around_y(min, max)).hypot(1.0);
+                sdf.lipschitz() * if stretch.is_finite() { stretch } else { 4.0 }
+            }
+            _ => 1.0,
+        }
+    }
+}
+
+/// The largest distance from the Y axis of a point in the box.
+fn radius_around_y(min: Vec3, max: Vec3) -> f32 {
+    let corner = min.abs().max(max.abs());
+    corner.x.hypot(corner.z)
+}
+
+fn union_bounds(
+    (a_min, a_max): (Vec3, Vec3),
+    (b_min, b_max): (Vec3, Vec3),
+    margin: f32,
+) -> (Vec3, Vec3) {
+    (a_min.min(b_min) - margin, a_max.max(b_max) + margin)
+}
+
+/// A surface defined by a signed distance function, found by sphere tracing:
+/// stepping along the ray by the distance to the surface until it is closer
+/// than `epsilon`.
+#[derive(Clone)]
+pub struct SdfShape {
+    pub sdf: Sdf,
+    pub min: Vec3,
+    pub max: Vec3,
+    pub epsilon: f32,
+    pub max_steps: u32,
+    /// The fraction of the distance stepped at a time, below one for
+    /// functions that overestimate the distance.
+    pub step_scale: f32,
+    material: Material,
+}
+
+impl SdfShape {
+    pub fn new(sdf: Sdf, material: Material) -> Self {
+        let (min, max) = sdf.bounds();
+        Self {
+            step_scale: 1.0 / sdf.lipschitz(),
+            sdf,
+            min,
+            max,
+            epsilon: 1e-4,
+            max_steps: 512,
+            material,
+        }
+    }
+
+    pub fn with_bounds(mut self, min: Vec3, max: Vec3) -> Self {
+        self.min = min;
+        self.max = max;
+        self
+    }
+
+    /// The part of the ray inside the bounds, padded so surfaces lying on
+    /// them are still found.
+    fn clip(&self, ray: &Ray, interval: Range<f32>) -> Option<Range<f32>> {
+        let padding = Vec3::splat(self.epsilon * 2.0);
+        let inverse_direction = ray.direction.recip();
+        let t1 = (self.min - padding - ray.origin) * inverse_direction;
+        let t2 = (self.max + padding - ray.origin) * inverse_direction;
+        let t_near = t1.min(t2).max_element().max(interval.start);
+        let t_far = t1.max(t2).min_element().min(interval.end);
+
+        (t_near < t_far && t_far.is_finite()).then_some(t_near..t_far)
+    }
+}
+
+impl Hittable for SdfShape {
+    fn hits(&self, ray: &Ray, interval: Range<f32>) -> Option<HitRecord<'_>> {
+        let interval = self.clip(ray, interval)?;
+        let speed = ray.direction.length();
+        let mut t = interval.start;
+        // Tracing works from either side of the surface, so rays that start
+        // inside find their way out.
+        let side = self.sdf.distance(ray.at(t)).signum();
+
+        for _ in 0..self.max_steps {
+            let point = ray.at(t);
+            let distance = side * self.sdf.distance(point);
+            if distance < self.epsilon {
+                // Near the surface only count it when moving towards it, so
+                // rays leaving the surface they start on get away from it.
+                let normal = self.sdf.normal(point, self.epsilon);
+                if side * normal.dot(ray.direction) < 0.0 {
+                    let mut hit_record = HitRecord::new(t, point, ray, normal, &self.material);
+                    hit_record.set_uv(Sphere::get_uv(normal));
+                    return Some(hit_record);
+                }
+            }
+
+            t += distance.max(self.epsilon) * self.step_scale / speed;
+            if t >= interval.end {
+                return None;
+            }
+        }
+
+        None
+    }
+
+    /// Hits are only found to within `epsilon` of the surface, so the search
+    /// for the next one starts once the ray is twice that far through it.
+    fn hits_all(&self, ray: &Ray, interval: Range<f32>) -> Vec<HitRecord<'_>> {
+        let mut hit_records = Vec::new();
+        let mut start = interval.start;
+        let speed = ray.direction.length();
+        while let Some(hit_record) = self.hits(ray, start..interval.end) {
+            let cosine = hit_record.geometric_normal.dot(ray.direction).abs() / speed;
+            let through = hit_record.t + self.epsilon * 2.0 / (speed * cosine.max(0.01));
+            start = through.max(next_start(ray, hit_record.t));
+            hit_records.push(hit_record);
+        }
+
+        hit_records
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(sdf: Sdf, origin: Vec3, direction: Vec3) -> Option<(f32, bool)> {
+        let shape = SdfShape::new(sdf, Material::default());
+        let ray = Ray::new(origin, direction);
+        let hit_record = shape.hits(&ray, 0.0..f32::INFINITY)?;
+        Some((hit_record.t, hit_record.front_face))
+    }
+
+    fn assert_hit(hit: Option<(f32, bool)>, t: f32, front_face: bool) {
+        let (hit_t, hit_front_face) = hit.expect("expected a hit");
+        assert!((hit_t - t).abs() < 1e-3, "hit at {} instead of {}", hit_t, t);
+        assert_eq!(hit_front_face, front_face);
+    }
+
+    #[test]
+    fn hits_from_outside_and_inside() {
+        let sphere = || Sdf::sphere(1.0);
+        assert_hit(hit(sphere(), Vec3::X * -5.0, Vec3::X), 4.0, true);
+        assert_hit(hit(sphere(), Vec3::X * -5.0, Vec3::X * 2.0), 2.0, true);
+        assert_hit(hit(sphere(), Vec3::ZERO, Vec3::Y), 1.0, false);
+        assert!(hit(sphere(), Vec3::X * -5.0, Vec3::Y).is_none());
+    }
+
+    #[test]
+    fn hits_the_inside_of_a_hollow() {
+        let hollow = Sdf::cuboid(Vec3::splat(4.0)).subtraction(Sdf::sphere(1.0));
+        assert_hit(hit(hollow.clone(), Vec3::ZERO, Vec3::Z), 1.0, true);
+        assert_hit(hit(hollow, Vec3::Z * 1.5, Vec3::Z), 0.5, false);
+    }
+
+    #[test]
+    fn scales_by_the_size_of_the_factor() {
+        for factor in [2.0, -2.0] {
+            assert_hit(hit(Sdf::sphere(1.0).scale(factor), Vec3::X * -5.0, Vec3::X), 3.0, true);
+        }
+
+        let (min, max) = Sdf::Scale(-2.0, Box::new(Sdf::cuboid(Vec3::ONE))).bounds();
+        assert!(min.cmplt(max).all());
+        assert_hit(
+            hit(Sdf::Scale(-2.0, Box::new(Sdf::sphere(1.0))), Vec3::X * -5.0, Vec3::X),
+            3.0,
+            true,
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_a_zero_scale() {
+        Sdf::sphere(1.0).scale(0.0);
+    }
+}
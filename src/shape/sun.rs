@@ -0,0 +1,60 @@
+use rand::Rng;
+use std::f32::consts::TAU;
+
+use crate::pdf::orthonormal_basis::OrthonormalBasis;
+pub use crate::shape::hittable::*;
+
+/// A disk infinitely far away in `direction`, such as the sun of a `Sky`.
+/// It is drawn by the background, so rays never hit it; it only exists to
+/// be sampled as a light through `Scene::light`.
+#[derive(Clone)]
+pub struct Sun {
+    pub direction: Vec3,
+    pub angular_radius: f32,
+    basis: OrthonormalBasis,
+}
+
+impl Sun {
+    pub fn new(direction: Vec3, angular_radius: f32) -> Self {
+        Self {
+            direction: direction.normalize(),
+            angular_radius,
+            basis: OrthonormalBasis::new(&direction),
+        }
+    }
+
+    /// One minus the cosine of the angular radius, computed without the
+    /// cancellation of subtracting two numbers close to one.
+    fn one_minus_cos_max(&self) -> f32 {
+        2.0 * (self.angular_radius / 2.0).sin().powi(2)
+    }
+}
+
+impl Hittable for Sun {
+    fn hits(&self, _ray: &Ray, _interval: Range<f32>) -> Option<HitRecord<'_>> {
+        None
+    }
+
+    fn pdf_value(&self, _origin: &Vec3, direction: &Vec3) -> f32 {
+        let cos_theta = direction.normalize().dot(self.direction);
+        if 1.0 - cos_theta <= self.one_minus_cos_max() {
+            1.0 / (TAU * self.one_minus_cos_max())
+        } else {
+            0.0
+        }
+    }
+
+    fn random(&self, _origin: &Vec3) -> Vec3 {
+        let mut rng = rand::thread_rng();
+        let one_minus_cos_theta = rng.random::<f32>() * self.one_minus_cos_max();
+        let cos_theta = 1.0 - one_minus_cos_theta;
+        let sin_theta = (one_minus_cos_theta * (2.0 - one_minus_cos_theta)).sqrt();
+        let phi = TAU * rng.random::<f32>();
+
+        self.basis.transform(&Vec3::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ))
+    }
+}
@@ -0,0 +1,261 @@
+use rand::Rng;
+use std::f32::consts::{PI, TAU};
+
+use crate::pdf::orthonormal_basis::OrthonormalBasis;
+pub use crate::shape::hittable::*;
+
+/// A torus around `axis`. `major_radius` is the distance from the center to
+/// the middle of the tube and `minor_radius` the radius of the tube.
+#[derive(Clone)]
+pub struct Torus {
+    pub center: Vec3,
+    pub major_radius: f32,
+    pub minor_radius: f32,
+    basis: OrthonormalBasis,
+    material: Material,
+}
+
+impl Torus {
+    pub fn new(
+        center: Vec3,
+        axis: Vec3,
+        major_radius: f32,
+        minor_radius: f32,
+        material: Material,
+    ) -> Self {
+        Self {
+            center,
+            major_radius,
+            minor_radius,
+            basis: OrthonormalBasis::new(&axis),
+            material,
+        }
+    }
+
+    pub fn area(&self) -> f32 {
+        4.0 * PI * PI * self.major_radius * self.minor_radius
+    }
+}
+
+impl Hittable for Torus {
+    fn hits(&self, ray: &Ray, interval: Range<f32>) -> Option<HitRecord<'_>> {
+        // The quartic is solved in double precision for a unit direction
+        // starting from the point of the ray closest to the center, with
+        // lengths scaled so the torus fits in the unit sphere. This keeps the
+        // coefficients well conditioned for distant origins and any size.
+        let scale = ray.direction.length() as f64;
+        let size = (self.major_radius + self.minor_radius) as f64;
+        let direction = self.basis.to_local(&ray.direction).as_dvec3() / scale;
+        let origin = self.basis.to_local(&(ray.origin - self.center)).as_dvec3() / size;
+        let offset = -origin.dot(direction);
+        let origin = origin + direction * offset;
+        if origin.length_squared() > 1.0 {
+            return None;
+        }
+
+        let major_squared = (self.major_radius as f64 / size).powi(2);
+        let minor_squared = (self.minor_radius as f64 / size).powi(2);
+        let f = origin.dot(direction);
+        let g = origin.length_squared() + major_squared - minor_squared;
+        let coefficients = [
+            g * g - 4.0 * major_squared * (origin.x * origin.x + origin.y * origin.y),
+            4.0 * f * g - 8.0 * major_squared * (origin.x * direction.x + origin.y * direction.y),
+            4.0 * f * f + 2.0 * g
+                - 4.0 * major_squared * (direction.x * direction.x + direction.y * direction.y),
+            4.0 * f,
+            1.0,
+        ];
+
+        let t = solve_quartic(coefficients)
+            .into_iter()
+            .map(|root| ((root + offset) * size / scale) as f32)
+            .filter(|t| interval.contains(t))
+            .min_by(f32::total_cmp)?;
+
+        let hit_point = ray.at(t);
+        let local = self.basis.to_local(&(hit_point - self.center));
+        let ring = Vec3::new(local.x, local.y, 0.0).normalize_or_zero() * self.major_radius;
+        let normal = (local - ring).normalize_or_zero();
+        let outward_normal = self.basis.transform(&normal);
+        let mut hit_record = HitRecord::new(t, hit_point, ray, outward_normal, &self.material);
+        let tube_distance = local.x.hypot(local.y) - self.major_radius;
+        hit_record.set_uv((
+            0.5 + local.y.atan2(local.x) / TAU,
+            0.5 - local.z.atan2(tube_distance) / TAU,
+        ));
+
+        Some(hit_record)
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        area_pdf_value(self, self.area(), origin, direction)
+    }
+
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        let mut rng = rand::thread_rng();
+        // The outside of the tube has more area than the inside, so tube angles
+        // are accepted in proportion to their distance from the axis.
+        let tube_angle = loop {
+            let angle = TAU * rng.random::<f32>();
+            let weight = (self.major_radius + self.minor_radius * angle.cos())
+                / (self.major_radius + self.minor_radius);
+            if rng.random::<f32>() <= weight {
+                break angle;
+            }
+        };
+        let ring_angle = TAU * rng.random::<f32>();
+        let outward = Vec3::new(ring_angle.cos(), ring_angle.sin(), 0.0);
+        let local = outward * (self.major_radius + self.minor_radius * tube_angle.cos())
+            + Vec3::Z * self.minor_radius * tube_angle.sin();
+
+        (self.center + self.basis.transform(&local) - *origin).normalize()
+    }
+}
+
+/// Real roots of `c[4] x⁴ + c[3] x³ + c[2] x² + c[1] x + c[0]`, found with
+/// Ferrari's method and refined with Newton's method.
+fn solve_quartic(c: [f64; 5]) -> Vec<f64> {
+    let a = c[3] / c[4];
+    let b = c[2] / c[4];
+    let c1 = c[1] / c[4];
+    let d = c[0] / c[4];
+
+    // Substituting x = y - a / 4 gives y⁴ + p y² + q y + r.
+    let a_squared = a * a;
+    let p = -3.0 / 8.0 * a_squared + b;
+    let q = a_squared * a / 8.0 - a * b / 2.0 + c1;
+    let r = -3.0 / 256.0 * a_squared * a_squared + a_squared * b / 16.0 - a * c1 / 4.0 + d;
+
+    let mut roots = Vec::with_capacity(4);
+    if r.abs() < 1e-12 {
+        roots.extend(solve_cubic([q, p, 0.0, 1.0]));
+        roots.push(0.0);
+    } else {
+        // The largest root of the resolvent cubic keeps 2 z - p non-negative.
+        let z = solve_cubic([r * p / 2.0 - q * q / 8.0, -r, -p / 2.0, 1.0])
+            .into_iter()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let u = z * z - r;
+        let v = 2.0 * z - p;
+        let u = if u.abs() < 1e-12 { 0.0 } else if u > 0.0 { u.sqrt() } else { return roots };
+        let v = if v.abs() < 1e-12 { 0.0 } else if v > 0.0 { v.sqrt() } else { return roots };
+        let v = if q < 0.0 { -v } else { v };
+        roots.extend(solve_quadratic(z - u, v));
+        roots.extend(solve_quadratic(z + u, -v));
+    }
+
+    roots
+        .into_iter()
+        .map(|root| {
+            let mut x = root - a / 4.0;
+            for _ in 0..2 {
+                let value = (((c[4] * x + c[3]) * x + c[2]) * x + c[1]) * x + c[0];
+                let slope = ((4.0 * c[4] * x + 3.0 * c[3]) * x + 2.0 * c[2]) * x + c[1];
+                if slope.abs() > 1e-12 {
+                    x -= value / slope;
+                }
+            }
+            x
+        })
+        .collect()
+}
+
+/// Real roots of `x² + b x + c`.
+fn solve_quadratic(c: f64, b: f64) -> Vec<f64> {
+    let half_b = b / 2.0;
+    let discriminant = half_b * half_b - c;
+    if discriminant.abs() < 1e-12 {
+        vec![-half_b]
+    } else if discriminant < 0.0 {
+        Vec::new()
+    } else {
+        let root = discriminant.sqrt();
+        vec![-half_b - root, -half_b + root]
+    }
+}
+
+/// Real roots of `c[3] x³ + c[2] x² + c[1] x + c[0]`, using Cardano's formula
+/// or the trigonometric form when there are three of them.
+fn solve_cubic(c: [f64; 4]) -> Vec<f64> {
+    let a = c[2] / c[3];
+    let b = c[1] / c[3];
+    let c0 = c[0] / c[3];
+
+    // Substituting x = y - a / 3 gives y³ + 3 p y + 2 q.
+    let a_squared = a * a;
+    let p = (-a_squared / 3.0 + b) / 3.0;
+    let q = (2.0 / 27.0 * a * a_squared - a * b / 3.0 + c0) / 2.0;
+    let p_cubed = p * p * p;
+    let discriminant = q * q + p_cubed;
+
+    let roots = if discriminant.abs() < 1e-12 {
+        if q.abs() < 1e-12 {
+            vec![0.0]
+        } else {
+            let u = (-q).cbrt();
+            vec![2.0 * u, -u]
+        }
+    } else if discriminant < 0.0 {
+        let phi = (-q / (-p_cubed).sqrt()).clamp(-1.0, 1.0).acos() / 3.0;
+        let t = 2.0 * (-p).sqrt();
+        vec![
+            t * phi.cos(),
+            -t * (phi + std::f64::consts::FRAC_PI_3).cos(),
+            -t * (phi - std::f64::consts::FRAC_PI_3).cos(),
+        ]
+    } else {
+        let root = discriminant.sqrt();
+        vec![(root - q).cbrt() - (root + q).cbrt()]
+    };
+
+    roots.into_iter().map(|root| root - a / 3.0).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn torus() -> Torus {
+        Torus::new(Vec3::ZERO, Vec3::Y, 1.0, 0.25, Material::default())
+    }
+
+    #[test]
+    fn solves_quartics_with_four_real_roots() {
+        let mut roots = solve_quartic([24.0, -50.0, 35.0, -10.0, 1.0]);
+        roots.sort_by(f64::total_cmp);
+
+        assert_eq!(roots.len(), 4);
+        for (root, expected) in roots.iter().zip([1.0, 2.0, 3.0, 4.0]) {
+            assert!((root - expected).abs() < 1e-9, "{:?}", roots);
+        }
+    }
+
+    #[test]
+    fn crosses_both_sides_of_the_tube() {
+        let torus = torus();
+        for (speed, origin) in [(1.0, -5.0), (2.0, -5.0), (1.0, -5e3)] {
+            let ray = Ray::new(Vec3::new(origin, 0.0, 0.0), Vec3::X * speed);
+            let mut hit_records = Vec::new();
+            let mut start = 0.0;
+            while let Some(hit_record) = torus.hits(&ray, start..f32::INFINITY) {
+                start = hit_record.t + 0.1 / speed;
+                hit_records.push(hit_record);
+            }
+            let distances = [-1.25, -0.75, 0.75, 1.25].map(|x| (x - origin) / speed);
+
+            assert_eq!(hit_records.len(), 4);
+            for ((hit_record, t), front_face) in
+                hit_records.iter().zip(distances).zip([true, false, true, false])
+            {
+                assert!((hit_record.t - t).abs() < 1e-4 * t, "{} != {}", hit_record.t, t);
+                assert_eq!(hit_record.front_face, front_face);
+            }
+        }
+    }
+
+    #[test]
+    fn misses_through_the_hole() {
+        let ray = Ray::new(Vec3::new(0.0, -5.0, 0.0), Vec3::Y);
+        assert!(torus().hits(&ray, 0.0..f32::INFINITY).is_none());
+    }
+}
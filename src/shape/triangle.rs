@@ -1,60 +1,154 @@
+use rand::Rng;
+
 pub use crate::shape::hittable::*;
+use crate::shape::triangle_mesh::{TriangleMesh, Vec2};
+use crate::texture::*;
 pub use glam::Mat4;
 
-#[derive(Clone)]
+#[derive(Clone, Copy)]
 pub struct Triangle {
-    pub p1: Vec3,
-    pub p2: Vec3,
-    pub p3: Vec3,
-    normal: Vec3,
-    denominator: f32,
-    material: Material,
+    pub vertices: [u32; 3],
+    pub material: u32,
 }
 
 impl Triangle {
-    pub fn new(p1: Vec3, p2: Vec3, p3: Vec3, material: Material) -> Self {
+    pub fn new(vertices: [u32; 3], material: u32) -> Self {
+        Self { vertices, material }
+    }
+
+    pub fn positions(&self, vertices: &[Vec3]) -> [Vec3; 3] {
+        self.vertices.map(|index| vertices[index as usize])
+    }
+
+    pub fn centroid(&self, vertices: &[Vec3]) -> Vec3 {
+        let [p1, p2, p3] = self.positions(vertices);
+        (p1 + p2 + p3) / 3.0
+    }
+
+    pub fn area(&self, vertices: &[Vec3]) -> f32 {
+        let [p1, p2, p3] = self.positions(vertices);
+        0.5 * (p2 - p1).cross(p3 - p1).length()
+    }
+
+    /// A point spread uniformly over the area of the triangle.
+    pub fn random_point(&self, vertices: &[Vec3]) -> Vec3 {
+        let mut rng = rand::thread_rng();
+        let [p1, p2, p3] = self.positions(vertices);
+        let r1 = rng.random::<f32>().sqrt();
+        let r2 = rng.random::<f32>();
+        p1 * (1.0 - r1) + p2 * (r1 * (1.0 - r2)) + p3 * (r1 * r2)
+    }
+
+    pub fn interpolate<T>(&self, attributes: &[T], c1: f32, c2: f32) -> T
+    where
+        T: Copy + std::ops::Mul<f32, Output = T> + std::ops::Add<Output = T>,
+    {
+        let [a1, a2, a3] = self.vertices.map(|index| attributes[index as usize]);
+        a1 * (1.0 - c1 - c2) + a2 * c1 + a3 * c2
+    }
+
+    pub fn hits<'a>(
+        &self,
+        mesh: &TriangleMesh,
+        material: &'a Material,
+        normal_map: Option<&Texture>,
+        ray: &Ray,
+        interval: Range<f32>,
+    ) -> Option<HitRecord<'a>> {
+        let [p1, p2, p3] = mesh.positions(self);
         let r1 = p2 - p1;
         let r2 = p3 - p1;
-        let cross_r1_r2 = r1.cross(r2);
-        let normal = cross_r1_r2.normalize();
-        let denominator = normal.dot(cross_r1_r2);
-        Self {
-            p1,
-            p2,
-            p3,
-            denominator,
-            normal,
-            material,
+        let p = ray.direction.cross(r2);
+        let determinant = r1.dot(p);
+        // The determinant scales with the square of the edge lengths, so any
+        // fixed cutoff would hide small triangles. Rays nearly parallel to
+        // the plane get non-finite coordinates, which fail the tests below.
+        if determinant == 0.0 {
+            return None;
         }
-    }
 
-    pub fn transform(&mut self, transform_matrix: &Mat4) {
-        self.p1 = transform_matrix.transform_point3(self.p1);
-        self.p2 = transform_matrix.transform_point3(self.p2);
-        self.p3 = transform_matrix.transform_point3(self.p3);
-        let r1 = self.p2 - self.p1;
-        let r2 = self.p3 - self.p1;
-        let cross_r1_r2 = r1.cross(r2);
-        self.normal = cross_r1_r2.normalize();
-        self.denominator = self.normal.dot(cross_r1_r2);
-    }
-}
+        let inverse_determinant = 1.0 / determinant;
+        let s = ray.origin - p1;
+        let c1 = s.dot(p) * inverse_determinant;
+        if !(0.0..=1.0).contains(&c1) {
+            return None;
+        }
 
-impl Hittable for Triangle {
-    fn hits(&self, ray: &Ray, interval: Range<f32>) -> Option<HitRecord> {
-        let t = self.normal.dot(self.p1 - ray.origin) / self.normal.dot(ray.direction);
-        if !interval.contains(&t) {
+        let q = s.cross(r1);
+        let c2 = ray.direction.dot(q) * inverse_determinant;
+        if c2 < 0.0 || c1 + c2 > 1.0 {
             return None;
         }
 
-        let hit_point = ray.at(t);
-        let p1_hp = self.p1 - hit_point;
-        let c1 = self.normal.dot((self.p3 - hit_point).cross(p1_hp)) / self.denominator;
-        let c2 = self.normal.dot(p1_hp.cross(self.p2 - hit_point)) / self.denominator;
-        if c1 < 0.0 || c2 < 0.0 || c1 + c2 > 1.0 {
+        let t = r2.dot(q) * inverse_determinant;
+        if !interval.contains(&t) {
             return None;
         }
 
-        Some(HitRecord::new(t, hit_point, &ray, self.normal, &self.material))
+        let normal = r1.cross(r2).normalize();
+        let mut hit_record = HitRecord::new(t, ray.at(t), ray, normal, material);
+        let uv = match mesh.uvs.as_ref() {
+            Some(uvs) => self.interpolate(uvs, c1, c2),
+            None => Vec2::new(c1, c2),
+        };
+        hit_record.set_uv(uv.into());
+
+        let shading_normal = mesh
+            .normals
+            .as_ref()
+            .map(|normals| self.interpolate(normals, c1, c2).normalize_or_zero())
+            .filter(|shading_normal| *shading_normal != Vec3::ZERO)
+            .unwrap_or(normal);
+        let shading_normal = match normal_map {
+            Some(normal_map) => self.map_normal(mesh, normal_map, shading_normal, uv, c1, c2),
+            None => shading_normal,
+        };
+        hit_record.set_shading_normal(shading_normal);
+
+        Some(hit_record)
+    }
+
+    /// Perturbs `normal` with a tangent-space normal map. Without per-vertex
+    /// tangents, the tangent frame is derived from the texture coordinates.
+    fn map_normal(
+        &self,
+        mesh: &TriangleMesh,
+        normal_map: &Texture,
+        normal: Vec3,
+        uv: Vec2,
+        c1: f32,
+        c2: f32,
+    ) -> Vec3 {
+        let (tangent, handedness) = match (mesh.tangents.as_ref(), mesh.uvs.as_ref()) {
+            (Some(tangents), _) => {
+                let tangent = self.interpolate(tangents, c1, c2);
+                (tangent.truncate(), tangent.w.signum())
+            }
+            (None, Some(uvs)) => {
+                let [p1, p2, p3] = mesh.positions(self);
+                let [uv1, uv2, uv3] = self.vertices.map(|index| uvs[index as usize]);
+                let (edge1, edge2) = (p2 - p1, p3 - p1);
+                let (delta1, delta2) = (uv2 - uv1, uv3 - uv1);
+                let determinant = delta1.x * delta2.y - delta1.y * delta2.x;
+                if determinant == 0.0 {
+                    return normal;
+                }
+                let tangent = (edge1 * delta2.y - edge2 * delta1.y) / determinant;
+                // Texture coordinates grow downwards, so the bitangent of the
+                // normal map points along decreasing v.
+                let bitangent = (edge1 * delta2.x - edge2 * delta1.x) / determinant;
+                (tangent, normal.cross(tangent).dot(bitangent).signum())
+            }
+            (None, None) => return normal,
+        };
+
+        let tangent = (tangent - normal * normal.dot(tangent)).normalize_or_zero();
+        if tangent == Vec3::ZERO {
+            return normal;
+        }
+
+        let bitangent = normal.cross(tangent) * handedness;
+        let mapped = normal_map.get_color_value(uv.x, uv.y, Vec3::ZERO) * 2.0 - Vec3::ONE;
+        (tangent * mapped.x + bitangent * mapped.y + normal * mapped.z).normalize_or(normal)
     }
 }
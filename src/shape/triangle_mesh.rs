@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+pub use crate::shape::triangle::*;
+pub use glam::{Vec2, Vec4};
+
+pub const DEFAULT_CREASE_ANGLE: f32 = 60.0;
+
+#[derive(Clone, Default)]
+pub struct TriangleMesh {
+    pub positions: Vec<Vec3>,
+    pub normals: Option<Vec<Vec3>>,
+    pub uvs: Option<Vec<Vec2>>,
+    pub tangents: Option<Vec<Vec4>>,
+    pub colors: Option<Vec<Vec3>>,
+    pub triangles: Vec<Triangle>,
+}
+
+impl TriangleMesh {
+    pub fn new(positions: Vec<Vec3>, triangles: Vec<Triangle>) -> Self {
+        Self {
+            positions,
+            triangles,
+            ..Default::default()
+        }
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    pub fn positions(&self, triangle: &Triangle) -> [Vec3; 3] {
+        triangle.positions(&self.positions)
+    }
+
+    /// Appends the vertices and triangles of `other`. Attributes present in
+    /// only one of the meshes are filled with defaults for the other.
+    pub fn append(&mut self, other: TriangleMesh) {
+        let offset = self.positions.len() as u32;
+        let (count, other_count) = (self.positions.len(), other.positions.len());
+        append_attribute(&mut self.normals, other.normals, count, other_count, Vec3::ZERO);
+        append_attribute(&mut self.uvs, other.uvs, count, other_count, Vec2::ZERO);
+        append_attribute(&mut self.tangents, other.tangents, count, other_count, Vec4::ZERO);
+        append_attribute(&mut self.colors, other.colors, count, other_count, Vec3::ONE);
+        self.positions.extend(other.positions);
+        self.triangles.extend(other.triangles.into_iter().map(|triangle| {
+            Triangle::new(triangle.vertices.map(|index| index + offset), triangle.material)
+        }));
+    }
+
+    pub fn transform(&mut self, transform_matrix: &Mat4) {
+        for position in self.positions.iter_mut() {
+            *position = transform_matrix.transform_point3(*position);
+        }
+
+        let normal_matrix = transform_matrix.inverse().transpose();
+        if let Some(normals) = self.normals.as_mut() {
+            for normal in normals.iter_mut() {
+                *normal = normal_matrix.transform_vector3(*normal).normalize_or_zero();
+            }
+        }
+
+        if let Some(tangents) = self.tangents.as_mut() {
+            for tangent in tangents.iter_mut() {
+                let direction = transform_matrix
+                    .transform_vector3(tangent.truncate())
+                    .normalize_or_zero();
+                *tangent = direction.extend(tangent.w);
+            }
+        }
+    }
+
+    pub fn face_normal(&self, triangle: &Triangle) -> Vec3 {
+        let [p1, p2, p3] = self.positions(triangle);
+        (p2 - p1).cross(p3 - p1).normalize_or_zero()
+    }
+
+    /// Computes angle-weighted vertex normals. Faces meeting at more than
+    /// `crease_angle` degrees are not smoothed together, which splits the
+    /// vertices they share.
+    pub fn compute_normals(&mut self, crease_angle: f32) {
+        let cos_crease = crease_angle.to_radians().cos();
+        let face_normals: Vec<Vec3> = self.triangles.iter()
+            .map(|triangle| self.face_normal(triangle))
+            .collect();
+        let corner_angles: Vec<[f32; 3]> = self.triangles.iter()
+            .map(|triangle| {
+                let p = self.positions(triangle);
+                [0, 1, 2].map(|k| {
+                    let edge1 = p[(k + 1) % 3] - p[k];
+                    let edge2 = p[(k + 2) % 3] - p[k];
+                    edge1.angle_between(edge2)
+                })
+            })
+            .collect();
+
+        let mut incident_corners = vec![Vec::new(); self.positions.len()];
+        for (triangle_index, triangle) in self.triangles.iter().enumerate() {
+            for (corner, vertex) in triangle.vertices.iter().enumerate() {
+                incident_corners[*vertex as usize].push((triangle_index, corner));
+            }
+        }
+
+        let mut normals: Vec<Option<Vec3>> = vec![None; self.positions.len()];
+        let mut split_vertices = HashMap::new();
+        let mut triangles = self.triangles.clone();
+        for (triangle_index, triangle) in triangles.iter_mut().enumerate() {
+            let face_normal = face_normals[triangle_index];
+            for vertex in triangle.vertices.iter_mut() {
+                let normal = incident_corners[*vertex as usize]
+                    .iter()
+                    .filter(|(other, _)| face_normals[*other].dot(face_normal) >= cos_crease)
+                    .map(|(other, corner)| face_normals[*other] * corner_angles[*other][*corner])
+                    .sum::<Vec3>()
+                    .normalize_or_zero();
+                let normal = if normal == Vec3::ZERO { face_normal } else { normal };
+
+                match normals[*vertex as usize] {
+                    None => normals[*vertex as usize] = Some(normal),
+                    Some(existing) if existing == normal => {}
+                    Some(_) => {
+                        let key = (*vertex, normal.to_array().map(f32::to_bits));
+                        *vertex = *split_vertices.entry(key).or_insert_with(|| {
+                            normals.push(Some(normal));
+                            self.duplicate_vertex(*vertex)
+                        });
+                    }
+                }
+            }
+        }
+
+        self.triangles = triangles;
+        self.normals = Some(normals.into_iter().map(Option::unwrap_or_default).collect());
+    }
+
+    fn duplicate_vertex(&mut self, index: u32) -> u32 {
+        let index = index as usize;
+        self.positions.push(self.positions[index]);
+        if let Some(uvs) = self.uvs.as_mut() {
+            uvs.push(uvs[index]);
+        }
+        if let Some(tangents) = self.tangents.as_mut() {
+            tangents.push(tangents[index]);
+        }
+        if let Some(colors) = self.colors.as_mut() {
+            colors.push(colors[index]);
+        }
+
+        (self.positions.len() - 1) as u32
+    }
+}
+
+fn append_attribute<T: Clone>(
+    attribute: &mut Option<Vec<T>>,
+    other: Option<Vec<T>>,
+    count: usize,
+    other_count: usize,
+    default: T,
+) {
+    match other {
+        Some(other) => attribute.get_or_insert_with(|| vec![default; count]).extend(other),
+        None => {
+            if let Some(values) = attribute.as_mut() {
+                values.extend(std::iter::repeat_n(default, other_count));
+            }
+        }
+    }
+}
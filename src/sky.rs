@@ -0,0 +1,206 @@
+use glam::Vec3;
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use crate::shape::sun::Sun;
+use crate::shape::Shape;
+use crate::util;
+
+/// The angular radius of the sun seen from the ground, in radians.
+pub const SUN_ANGULAR_RADIUS: f32 = 0.00465;
+
+/// The luminance of the sun outside the atmosphere in kcd/m², the unit of
+/// the luminance of the sky in the Preetham model.
+const SUN_LUMINANCE: f32 = 1.6e6;
+
+/// The Preetham analytic daylight sky for a sun direction and turbidity, the
+/// haziness of the air from 2 for a clear day to about 10. Below the horizon
+/// it shows a flat ground of `ground_albedo` lit by the sky and the sun.
+///
+/// Luminances are scaled by `intensity`, which by default brings the zenith
+/// of a clear day to about one half.
+#[derive(Clone)]
+pub struct Sky {
+    sun_direction: Vec3,
+    turbidity: f32,
+    ground_albedo: Vec3,
+    intensity: f32,
+    perez: [[f32; 5]; 3],
+    zenith: Vec3,
+    sun_radiance: Vec3,
+    ground_radiance: Vec3,
+}
+
+impl Sky {
+    /// `sun_direction` points from the ground towards the sun.
+    pub fn new(sun_direction: Vec3, turbidity: f32, ground_albedo: Vec3) -> Self {
+        Self::with_intensity(sun_direction, turbidity, ground_albedo, 0.05)
+    }
+
+    pub fn with_intensity(
+        sun_direction: Vec3,
+        turbidity: f32,
+        ground_albedo: Vec3,
+        intensity: f32,
+    ) -> Self {
+        let sun_direction = sun_direction.normalize();
+        let t = turbidity.clamp(1.7, 10.0);
+        // The model is not meant for a sun below the horizon, so it stays
+        // just above it.
+        let sun_theta = sun_direction
+            .y
+            .clamp(-1.0, 1.0)
+            .acos()
+            .min(FRAC_PI_2 - 0.01);
+
+        let perez = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * sun_theta);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let theta = Vec3::new(sun_theta.powi(3), sun_theta.powi(2), sun_theta);
+        let zenith_x = t * t * theta.dot(Vec3::new(0.00166, -0.00375, 0.00209))
+            + t * (theta.dot(Vec3::new(-0.02903, 0.06377, -0.03202)) + 0.00394)
+            + theta.dot(Vec3::new(0.11693, -0.21196, 0.06052))
+            + 0.25886;
+        let zenith_y = t * t * theta.dot(Vec3::new(0.00275, -0.00610, 0.00317))
+            + t * (theta.dot(Vec3::new(-0.04214, 0.08970, -0.04153)) + 0.00516)
+            + theta.dot(Vec3::new(0.15346, -0.26756, 0.06670))
+            + 0.26688;
+
+        let mut sky = Self {
+            sun_direction,
+            turbidity: t,
+            ground_albedo,
+            intensity,
+            perez,
+            zenith: Vec3::new(zenith_luminance.max(0.0), zenith_x, zenith_y),
+            sun_radiance: Vec3::ZERO,
+            ground_radiance: Vec3::ZERO,
+        };
+        sky.sun_radiance = sky.sun_transmittance() * SUN_LUMINANCE * intensity;
+        sky.ground_radiance = ground_albedo * sky.horizontal_irradiance() / PI;
+
+        sky
+    }
+
+    pub fn sun_direction(&self) -> Vec3 {
+        self.sun_direction
+    }
+
+    pub fn turbidity(&self) -> f32 {
+        self.turbidity
+    }
+
+    pub fn ground_albedo(&self) -> Vec3 {
+        self.ground_albedo
+    }
+
+    pub fn intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    /// A light to put in `Scene::light` so the sun is sampled directly.
+    pub fn sun(&self) -> Shape {
+        Shape::Sun(Sun::new(self.sun_direction, SUN_ANGULAR_RADIUS))
+    }
+
+    pub fn radiance(&self, direction: Vec3) -> Vec3 {
+        let direction = direction.normalize();
+        if direction.y < 0.0 {
+            return self.ground_radiance;
+        }
+
+        let mut radiance = self.sky_radiance(direction);
+        if direction.dot(self.sun_direction) >= SUN_ANGULAR_RADIUS.cos() {
+            radiance += self.sun_radiance;
+        }
+
+        radiance
+    }
+
+    /// The sky alone, without the sun disk, from the luminance and
+    /// chromaticity of the zenith scaled by the Perez distribution.
+    fn sky_radiance(&self, direction: Vec3) -> Vec3 {
+        let cos_theta = direction.y.max(0.01);
+        let cos_gamma = direction.dot(self.sun_direction).clamp(-1.0, 1.0);
+        let sun_cos_theta = self.sun_direction.y.max(0.01);
+
+        let [luminance, x, y] = [0, 1, 2].map(|i| {
+            let relative = perez(self.perez[i], cos_theta, cos_gamma)
+                / perez(self.perez[i], 1.0, sun_cos_theta);
+            self.zenith[i] * relative
+        });
+
+        xyy_to_rgb(x, y, luminance * self.intensity).max(Vec3::ZERO)
+    }
+
+    /// The fraction of each color of sunlight left after crossing the
+    /// atmosphere, from Rayleigh and aerosol extinction over the air mass.
+    fn sun_transmittance(&self) -> Vec3 {
+        if self.sun_direction.y <= 0.0 {
+            return Vec3::ZERO;
+        }
+
+        let elevation = self.sun_direction.y.asin().to_degrees();
+        // Kasten and Young's air mass, which stays finite at the horizon.
+        let air_mass = 1.0 / (self.sun_direction.y + 0.50572 * (elevation + 6.07995).powf(-1.6364));
+        let wavelengths = Vec3::new(0.68, 0.55, 0.44);
+        let rayleigh = wavelengths.powf(-4.08) * 0.008735;
+        let aerosol = wavelengths.powf(-1.3) * (0.04608 * self.turbidity - 0.04586);
+
+        (-(rayleigh + aerosol) * air_mass).exp()
+    }
+
+    /// The light falling on a horizontal surface from the sky and the sun,
+    /// integrated numerically over the upper hemisphere.
+    fn horizontal_irradiance(&self) -> Vec3 {
+        let (rings, segments) = (16, 64);
+        let mut irradiance = Vec3::ZERO;
+        for ring in 0..rings {
+            let theta = (ring as f32 + 0.5) / rings as f32 * FRAC_PI_2;
+            let (sin_theta, cos_theta) = theta.sin_cos();
+            let ring_width = FRAC_PI_2 / rings as f32;
+            let solid_angle = sin_theta * ring_width * (TAU / segments as f32);
+            for segment in 0..segments {
+                let phi = (segment as f32 + 0.5) / segments as f32 * TAU;
+                let (sin_phi, cos_phi) = phi.sin_cos();
+                let direction = Vec3::new(sin_theta * cos_phi, cos_theta, sin_theta * sin_phi);
+                irradiance += self.sky_radiance(direction) * cos_theta * solid_angle;
+            }
+        }
+
+        let sun_solid_angle = TAU * (1.0 - SUN_ANGULAR_RADIUS.cos());
+        irradiance + self.sun_radiance * sun_solid_angle * self.sun_direction.y.max(0.0)
+    }
+}
+
+fn perez([a, b, c, d, e]: [f32; 5], cos_theta: f32, cos_gamma: f32) -> f32 {
+    let gamma = cos_gamma.acos();
+    let gradient = 1.0 + a * (b / cos_theta).exp();
+    gradient * (1.0 + c * (d * gamma).exp() + e * cos_gamma * cos_gamma)
+}
+
+fn xyy_to_rgb(x: f32, y: f32, luminance: f32) -> Vec3 {
+    util::xyz_to_rgb(Vec3::new(x * luminance / y, luminance, (1.0 - x - y) * luminance / y))
+}
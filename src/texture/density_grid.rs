@@ -0,0 +1,144 @@
+use glam::UVec3;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::error::{Error, Result};
+pub use crate::texture::*;
+
+/// Scalar values on a regular 3D grid spanning the box from `min` to `max`,
+/// interpolated trilinearly between the centers of the cells. Points outside
+/// the box have a value of zero.
+#[derive(Clone)]
+pub struct DensityGrid {
+    pub resolution: UVec3,
+    pub min: Vec3,
+    pub max: Vec3,
+    values: Arc<Vec<f32>>,
+    max_value: f32,
+}
+
+impl DensityGrid {
+    /// A grid from values ordered with x changing fastest, then y, then z,
+    /// which fails unless there is one value per cell.
+    pub fn new(resolution: UVec3, values: Vec<f32>, min: Vec3, max: Vec3) -> Result<Self> {
+        let cells = resolution.element_product() as usize;
+        if values.len() != cells {
+            return Err(Error::invalid(
+                "density grid",
+                format!(
+                    "expected {} values for a {}x{}x{} grid, found {}",
+                    cells,
+                    resolution.x,
+                    resolution.y,
+                    resolution.z,
+                    values.len()
+                ),
+            ));
+        }
+
+        Ok(Self::from_values(resolution, values, min, max))
+    }
+
+    fn from_values(resolution: UVec3, values: Vec<f32>, min: Vec3, max: Vec3) -> Self {
+        let max_value = values.iter().copied().fold(0.0, f32::max);
+        Self {
+            resolution,
+            min,
+            max,
+            values: Arc::new(values),
+            max_value,
+        }
+    }
+
+    /// Evaluates `density` at the center of every cell, for baking noise or
+    /// other procedural functions into a grid.
+    pub fn from_fn(
+        resolution: UVec3,
+        min: Vec3,
+        max: Vec3,
+        density: impl Fn(Vec3) -> f32,
+    ) -> Self {
+        let cell_size = (max - min) / resolution.as_vec3();
+        let mut values = Vec::with_capacity(resolution.element_product() as usize);
+        for z in 0..resolution.z {
+            for y in 0..resolution.y {
+                for x in 0..resolution.x {
+                    let cell = UVec3::new(x, y, z).as_vec3() + 0.5;
+                    values.push(density(min + cell * cell_size));
+                }
+            }
+        }
+        Self::from_values(resolution, values, min, max)
+    }
+
+    /// Loads a headerless file of little endian `f32` values in the order
+    /// used by `new`.
+    pub fn load_raw(path: &str, resolution: UVec3, min: Vec3, max: Vec3) -> Result<Self> {
+        let path = Path::new(path);
+        let bytes = std::fs::read(path).map_err(|error| Error::io(path, "density grid", error))?;
+        let expected = resolution.element_product() as usize * 4;
+        if bytes.len() != expected {
+            return Err(Error::parse(
+                path,
+                "density grid",
+                format!(
+                    "expected {} bytes for a {}x{}x{} grid, found {}",
+                    expected,
+                    resolution.x,
+                    resolution.y,
+                    resolution.z,
+                    bytes.len()
+                ),
+            ));
+        }
+
+        let values = bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        Ok(Self::from_values(resolution, values, min, max))
+    }
+
+    pub fn max_value(&self) -> f32 {
+        self.max_value
+    }
+
+    pub fn sample(&self, point: Vec3) -> f32 {
+        let position = (point - self.min) / (self.max - self.min) * self.resolution.as_vec3() - 0.5;
+        let size = self.resolution.as_vec3();
+        if position.cmplt(Vec3::splat(-1.0)).any() || position.cmpgt(size).any() {
+            return 0.0;
+        }
+
+        let base = position.floor();
+        let fraction = position - base;
+        let mut value = 0.0;
+        for corner in 0..8 {
+            let offset = Vec3::new(
+                (corner & 1) as f32,
+                ((corner >> 1) & 1) as f32,
+                ((corner >> 2) & 1) as f32,
+            );
+            let weight = (Vec3::ONE - offset + fraction * (2.0 * offset - 1.0)).element_product();
+            value += weight * self.value(base + offset);
+        }
+
+        value
+    }
+
+    fn value(&self, cell: Vec3) -> f32 {
+        if cell.cmplt(Vec3::ZERO).any() || cell.cmpge(self.resolution.as_vec3()).any() {
+            return 0.0;
+        }
+
+        let cell = cell.as_uvec3();
+        let index = (cell.z * self.resolution.y + cell.y) * self.resolution.x + cell.x;
+        self.values[index as usize]
+    }
+}
+
+impl ColorValue for DensityGrid {
+    fn get_color_value(&self, _: f32, _: f32, hit_point: Vec3) -> Vec3 {
+        Vec3::splat(self.sample(hit_point))
+    }
+}
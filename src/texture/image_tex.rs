@@ -1,3 +1,6 @@
+use std::path::Path;
+
+use crate::error::{Error, Result};
 pub use crate::texture::*;
 use image::ImageReader;
 pub use image::Rgb32FImage;
@@ -9,20 +12,28 @@ pub struct ImageTex {
 
 impl ImageTex {
     pub fn new(path: &str) -> Self {
-        let image = match ImageReader::open(path) {
-            Ok(reader) => Box::leak(Box::new(
-                reader
-                    .decode()
-                    .expect("Error: could not decode the texture file.")
-                    .into_rgb32f(),
-            )),
-            Err(e) => {
-                eprintln!("Error: could not open the texture file {}.", path);
-                panic!("Error loading image: {}", e)
-            }
-        };
-
-        Self { image }
+        Self::load(path).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        let path = Path::new(path);
+        let image = ImageReader::open(path)
+            .map_err(|error| Error::io(path, "texture", error))?
+            .decode()
+            .map_err(|error| Error::image(path, "texture", error))?
+            .into_rgb32f();
+
+        Ok(Self {
+            image: Box::leak(Box::new(image)),
+        })
+    }
+
+    pub fn from_memory(bytes: &[u8]) -> image::ImageResult<Self> {
+        let image = image::load_from_memory(bytes)?.into_rgb32f();
+
+        Ok(Self {
+            image: Box::leak(Box::new(image)),
+        })
     }
 }
 
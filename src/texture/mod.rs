@@ -1,6 +1,8 @@
 pub mod checkers;
+pub mod density_grid;
 pub mod image_tex;
 pub mod perlin;
+pub mod scaled;
 pub mod solid_color;
 
 pub use glam::f32::Vec3;
@@ -11,6 +13,8 @@ pub enum Texture {
     Checkers(checkers::Checkers),
     ImageTex(image_tex::ImageTex),
     Perlin(perlin::Perlin),
+    DensityGrid(density_grid::DensityGrid),
+    Scaled(scaled::Scaled),
 }
 
 pub trait ColorValue {
@@ -24,6 +28,8 @@ impl ColorValue for Texture {
             Texture::ImageTex(image_tex) => image_tex.get_color_value(u, v, hit_point),
             Texture::Checkers(checkers) => checkers.get_color_value(u, v, hit_point),
             Texture::Perlin(perlin) => perlin.get_color_value(u, v, hit_point),
+            Texture::DensityGrid(grid) => grid.get_color_value(u, v, hit_point),
+            Texture::Scaled(scaled) => scaled.get_color_value(u, v, hit_point),
         }
     }
 }
@@ -53,7 +59,26 @@ impl Texture {
         Self::ImageTex(image_tex::ImageTex::new(path))
     }
 
+    pub fn try_image_tex(path: &str) -> crate::error::Result<Self> {
+        image_tex::ImageTex::load(path).map(Self::ImageTex)
+    }
+
+    pub fn image_tex_from_memory(bytes: &[u8]) -> image::ImageResult<Self> {
+        image_tex::ImageTex::from_memory(bytes).map(Self::ImageTex)
+    }
+
     pub fn perlin(scale: f32) -> Self {
         Self::Perlin(perlin::Perlin::new(scale))
     }
+
+    pub fn density_grid(grid: density_grid::DensityGrid) -> Self {
+        Self::DensityGrid(grid)
+    }
+
+    pub fn scaled(texture: Texture, factor: Vec3) -> Self {
+        Self::Scaled(scaled::Scaled {
+            texture: Box::new(texture),
+            factor,
+        })
+    }
 }
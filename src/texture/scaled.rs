@@ -0,0 +1,15 @@
+pub use crate::texture::*;
+
+/// Another texture multiplied by a color, for example an emissive texture by
+/// the strength it is given.
+#[derive(Clone)]
+pub struct Scaled {
+    pub texture: Box<Texture>,
+    pub factor: Vec3,
+}
+
+impl ColorValue for Scaled {
+    fn get_color_value(&self, u: f32, v: f32, hit_point: Vec3) -> Vec3 {
+        self.texture.get_color_value(u, v, hit_point) * self.factor
+    }
+}
@@ -75,3 +75,15 @@ pub fn random_in_unit_disk() -> Vec3 {
         }
     }
 }
+
+/// Converts CIE XYZ to linear sRGB, which can be negative for colors outside
+/// of its gamut.
+pub fn xyz_to_rgb(xyz: Vec3) -> Vec3 {
+    let to_rgb = glam::Mat3::from_cols(
+        Vec3::new(3.2406, -0.9689, 0.0557),
+        Vec3::new(-1.5372, 1.8758, -0.2040),
+        Vec3::new(-0.4986, 0.0415, 1.0570),
+    );
+
+    to_rgb * xyz
+}